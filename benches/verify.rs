@@ -0,0 +1,299 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cita_crypto_trait::CreateKey;
+use cita_secp256k1::{
+    pubkey_to_address, recover, recover_addresses, sign, verify_address, verify_public,
+    verify_public_prepared, KeyPair, Message, PreparedMessage, Signature, SignaturePool, SignerSet,
+    Verifier,
+};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+fn bench_verify_public(c: &mut Criterion) {
+    let keypair = KeyPair::gen_keypair();
+    let message = Message::hash("bench");
+    let sig = sign(keypair.privkey(), &message).unwrap();
+    let verifier = Verifier::new(*keypair.pubkey()).unwrap();
+
+    // verify_public copies the key behind a 0x04 tag and parses it on every
+    // call, a Verifier does both once
+    let mut group = c.benchmark_group("verify_public");
+    group.bench_function("parse_per_call", |b| {
+        b.iter(|| verify_public(black_box(keypair.pubkey()), black_box(&sig), &message).unwrap())
+    });
+    group.bench_function("parsed_once", |b| {
+        b.iter(|| verifier.verify(black_box(&sig), &message).unwrap())
+    });
+    group.finish();
+}
+
+fn bench_verify_public_vs_address(c: &mut Criterion) {
+    let keypair = KeyPair::gen_keypair();
+    let address = keypair.address();
+    let message = Message::hash("bench");
+    let sig = sign(keypair.privkey(), &message).unwrap();
+
+    let mut group = c.benchmark_group("verify_known_signer");
+    group.bench_function("verify_public", |b| {
+        b.iter(|| verify_public(black_box(keypair.pubkey()), &sig, &message).unwrap())
+    });
+    group.bench_function("verify_address", |b| {
+        b.iter(|| verify_address(black_box(&address), &sig, &message).unwrap())
+    });
+    group.finish();
+}
+
+fn half_invalid_stream() -> (KeyPair, Vec<Signature>) {
+    let keypair = KeyPair::gen_keypair();
+    let message = Message::hash("bench");
+    let sigs = (0..100)
+        .map(|i| {
+            let mut sig = sign(keypair.privkey(), &message).unwrap();
+            if i % 2 == 0 {
+                sig.0[32..64].copy_from_slice(&[0xff; 32]);
+            }
+            sig
+        })
+        .collect();
+    (keypair, sigs)
+}
+
+fn bench_verify_address_half_invalid(c: &mut Criterion) {
+    let (keypair, sigs) = half_invalid_stream();
+    let address = keypair.address();
+    let message = Message::hash("bench");
+
+    // what verify_address did before rejecting out of range signatures early
+    c.bench_function("recover_half_invalid", |b| {
+        b.iter(|| {
+            for sig in &sigs {
+                let _ = black_box(recover(sig, &message).map(|p| p == *keypair.pubkey()));
+            }
+        })
+    });
+    c.bench_function("verify_address_half_invalid", |b| {
+        b.iter(|| {
+            for sig in &sigs {
+                let _ = black_box(verify_address(&address, sig, &message));
+            }
+        })
+    });
+}
+
+/// 100 signatures where 30 come from 3 validators signing 10 each.
+fn block_with_repeated_signers() -> Vec<(Signature, Message)> {
+    let repeated: Vec<KeyPair> = (0..3).map(|_| KeyPair::gen_keypair()).collect();
+    (0..100)
+        .map(|i: u32| {
+            let message = Message::hash(i.to_string());
+            let sig = if i < 30 {
+                sign(repeated[i as usize % 3].privkey(), &message).unwrap()
+            } else {
+                sign(KeyPair::gen_keypair().privkey(), &message).unwrap()
+            };
+            (sig, message)
+        })
+        .collect()
+}
+
+/// 100 signatures where 30 are 3 validators' votes relayed 10 times each.
+fn block_with_relayed_votes() -> Vec<(Signature, Message)> {
+    let validators: Vec<KeyPair> = (0..3).map(|_| KeyPair::gen_keypair()).collect();
+    let vote = Message::hash("block");
+    let votes: Vec<Signature> = validators
+        .iter()
+        .map(|keypair| sign(keypair.privkey(), &vote).unwrap())
+        .collect();
+    (0..100)
+        .map(|i: u32| {
+            if i < 30 {
+                (votes[i as usize % 3].clone(), vote)
+            } else {
+                let message = Message::hash(i.to_string());
+                (
+                    sign(KeyPair::gen_keypair().privkey(), &message).unwrap(),
+                    message,
+                )
+            }
+        })
+        .collect()
+}
+
+fn bench_block_signers(c: &mut Criterion) {
+    let block = block_with_relayed_votes();
+
+    c.bench_function("block_signers_recover", |b| {
+        b.iter(|| {
+            for (sig, message) in &block {
+                black_box(pubkey_to_address(&recover(sig, message).unwrap()));
+            }
+        })
+    });
+    c.bench_function("block_signers_signer_set", |b| {
+        b.iter(|| {
+            let mut signers = SignerSet::new();
+            for (sig, message) in &block {
+                black_box(signers.add(sig, message).unwrap());
+            }
+        })
+    });
+}
+
+fn bench_recover_addresses(c: &mut Criterion) {
+    let block = block_with_repeated_signers();
+    let addresses = recover_addresses(&block).unwrap();
+
+    c.bench_function("block_verify_address", |b| {
+        b.iter(|| {
+            for ((sig, message), address) in block.iter().zip(&addresses) {
+                black_box(verify_address(address, sig, message).unwrap());
+            }
+        })
+    });
+    c.bench_function("block_recover_addresses", |b| {
+        b.iter(|| black_box(recover_addresses(&block).unwrap()))
+    });
+}
+
+fn bench_verifier(c: &mut Criterion) {
+    let keypair = KeyPair::gen_keypair();
+    let pairs: Vec<(Signature, Message)> = (0..10_000)
+        .map(|i: u32| {
+            let message = Message::hash(i.to_string());
+            (sign(keypair.privkey(), &message).unwrap(), message)
+        })
+        .collect();
+    let verifier = Verifier::new(*keypair.pubkey()).unwrap();
+
+    let mut group = c.benchmark_group("verify_10k_one_key");
+    group.sample_size(10);
+    group.bench_function("verify_public", |b| {
+        b.iter(|| {
+            for (sig, message) in &pairs {
+                black_box(verify_public(keypair.pubkey(), sig, message).unwrap());
+            }
+        })
+    });
+    group.bench_function("verifier", |b| {
+        b.iter(|| {
+            for (sig, message) in &pairs {
+                black_box(verifier.verify(sig, message).unwrap());
+            }
+        })
+    });
+    group.finish();
+}
+
+fn bench_prepared_message(c: &mut Criterion) {
+    let message = Message::hash("block");
+    let votes: Vec<_> = (0..100)
+        .map(|_| {
+            let keypair = KeyPair::gen_keypair();
+            let sig = sign(keypair.privkey(), &message).unwrap();
+            (*keypair.pubkey(), sig)
+        })
+        .collect();
+    // the validator set is known ahead of the votes
+    let verifiers: Vec<_> = votes
+        .iter()
+        .map(|(pubkey, _)| Verifier::new(*pubkey).unwrap())
+        .collect();
+    let prepared = PreparedMessage::new(&message).unwrap();
+
+    c.bench_function("votes_verify_public", |b| {
+        b.iter(|| {
+            for (pubkey, sig) in &votes {
+                black_box(verify_public(pubkey, sig, &message).unwrap());
+            }
+        })
+    });
+    c.bench_function("votes_verify_public_prepared", |b| {
+        b.iter(|| {
+            for (pubkey, sig) in &votes {
+                black_box(verify_public_prepared(pubkey, sig, &prepared).unwrap());
+            }
+        })
+    });
+    c.bench_function("votes_verifier_prepared", |b| {
+        b.iter(|| {
+            for (verifier, (_, sig)) in verifiers.iter().zip(&votes) {
+                black_box(verifier.verify_prepared(sig, &prepared).unwrap());
+            }
+        })
+    });
+}
+
+fn bench_signature_pool(c: &mut Criterion) {
+    let keypair = KeyPair::gen_keypair();
+    let wire: Vec<u8> = (0..10_000u32)
+        .flat_map(|i| {
+            sign(keypair.privkey(), &Message::hash(i.to_string()))
+                .unwrap()
+                .0
+                .to_vec()
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("decode_10k");
+    group.bench_function("new_signature", |b| {
+        b.iter(|| {
+            for chunk in wire.chunks_exact(65) {
+                black_box(Signature::from(chunk));
+            }
+        })
+    });
+    group.bench_function("signature_pool", |b| {
+        let mut pool = SignaturePool::new(16);
+        b.iter(|| {
+            for chunk in wire.chunks_exact(65) {
+                let mut sig = pool.acquire();
+                sig.0.copy_from_slice(chunk);
+                pool.release(black_box(sig));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn bench_sign_context(c: &mut Criterion) {
+    let keypair = KeyPair::gen_keypair();
+    let message = Message::hash("bench");
+    let secret = secp256k1::SecretKey::from_slice(&keypair.privkey().0).unwrap();
+    let secp_message = secp256k1::Message::from_slice(message.as_bytes()).unwrap();
+
+    c.bench_function("sign_shared_context", |b| {
+        b.iter(|| sign(black_box(keypair.privkey()), &message).unwrap())
+    });
+    // what sign would cost if it built a context per call
+    c.bench_function("sign_fresh_context", |b| {
+        b.iter(|| {
+            secp256k1::Secp256k1::signing_only()
+                .sign_ecdsa_recoverable(&secp_message, black_box(&secret))
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_verify_public,
+    bench_verify_public_vs_address,
+    bench_verify_address_half_invalid,
+    bench_block_signers,
+    bench_recover_addresses,
+    bench_verifier,
+    bench_prepared_message,
+    bench_signature_pool,
+    bench_sign_context
+);
+criterion_main!(benches);
@@ -0,0 +1,382 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::encoding::to_hex;
+use super::{keccak256, pubkey_to_address, Error, PubKey};
+use cita_types::H160;
+use std::fmt;
+use std::str::FromStr;
+use subtle::ConstantTimeEq;
+
+/// Account address, the last 20 bytes of the public key hash.
+///
+/// A distinct type rather than an alias of `H160`, so other 20-byte hashes
+/// can not be passed as an address by accident; converting needs an explicit
+/// `From`:
+///
+/// ```compile_fail
+/// use cita_secp256k1::{verify_address, Message, Signature};
+/// use cita_types::H160;
+///
+/// let not_an_address = H160::zero();
+/// verify_address(&not_an_address, &Signature::default(), &Message::default());
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Address(H160);
+
+impl Address {
+    pub fn zero() -> Address {
+        Address(H160::zero())
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+
+    pub fn as_fixed_bytes(&self) -> &[u8; 20] {
+        self.0.as_fixed_bytes()
+    }
+}
+
+impl From<H160> for Address {
+    fn from(hash: H160) -> Self {
+        Address(hash)
+    }
+}
+
+impl From<Address> for H160 {
+    fn from(address: Address) -> Self {
+        address.0
+    }
+}
+
+impl From<[u8; 20]> for Address {
+    fn from(bytes: [u8; 20]) -> Self {
+        Address(H160::from(bytes))
+    }
+}
+
+impl From<PubKey> for Address {
+    fn from(pubkey: PubKey) -> Self {
+        pubkey_to_address(&pubkey)
+    }
+}
+
+impl<'a> From<&'a PubKey> for Address {
+    fn from(pubkey: &'a PubKey) -> Self {
+        pubkey_to_address(pubkey)
+    }
+}
+
+impl AsRef<[u8]> for Address {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+impl FromStr for Address {
+    type Err = Error;
+
+    /// Parse 40 hex digits, with or without a `0x` prefix.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.strip_prefix("0x").unwrap_or(s);
+        H160::from_str(s)
+            .map(Address)
+            .map_err(|_| Error::InvalidAddress)
+    }
+}
+
+impl fmt::LowerHex for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+/// Letter case of the hex digits in a formatted address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressCase {
+    Lower,
+    Upper,
+    /// Mixed case checksum from EIP-55.
+    Checksum,
+}
+
+/// How `format_address` renders an address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressFormat {
+    /// Prepend `0x`.
+    pub prefix: bool,
+    pub case: AddressCase,
+}
+
+impl Default for AddressFormat {
+    fn default() -> Self {
+        AddressFormat {
+            prefix: true,
+            case: AddressCase::Lower,
+        }
+    }
+}
+
+/// Render an address as hex according to `opts`.
+pub fn format_address(addr: &Address, opts: AddressFormat) -> String {
+    let hex = match opts.case {
+        AddressCase::Lower => to_hex(addr.as_bytes()),
+        AddressCase::Upper => to_hex(addr.as_bytes()).to_uppercase(),
+        AddressCase::Checksum => checksum_hex(addr),
+    };
+    if opts.prefix {
+        format!("0x{}", hex)
+    } else {
+        hex
+    }
+}
+
+/// EIP-55: uppercase each letter whose nibble in keccak256(lowercase hex) is >= 8.
+fn checksum_hex(addr: &Address) -> String {
+    let lower = to_hex(addr.as_bytes());
+    let hash = keccak256(lower.as_bytes());
+
+    lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Compare two addresses in constant time, for authorization decisions.
+pub fn addresses_ct_eq(a: &Address, b: &Address) -> bool {
+    a.as_bytes().ct_eq(b.as_bytes()).into()
+}
+
+/// Count the leading zero bits of an address, 160 for the zero address.
+pub fn address_leading_zero_bits(addr: &Address) -> u32 {
+    let mut bits = 0;
+    for b in addr.as_bytes() {
+        bits += b.leading_zeros();
+        if *b != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+/// Parse a `0x` prefixed EIP-55 address.
+///
+/// All-lowercase input carries no checksum and is accepted as is, mixed case
+/// input must match its checksum.
+pub fn parse_checksummed(s: &str) -> Result<Address, Error> {
+    let hex = s.strip_prefix("0x").ok_or(Error::InvalidAddress)?;
+    // H160::from_str would take a second "0x" too
+    if hex.len() != 40 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(Error::InvalidAddress);
+    }
+    let address = H160::from_str(hex)
+        .map(Address)
+        .map_err(|_| Error::InvalidAddress)?;
+    if hex.bytes().any(|b| b.is_ascii_uppercase()) && checksum_hex(&address) != hex {
+        return Err(Error::InvalidAddress);
+    }
+    Ok(address)
+}
+
+/// Serde adapter writing addresses as EIP-55 checksummed `0x` strings.
+///
+/// Use with `#[serde(with = "cita_secp256k1::checksum_serde")]`; reading
+/// goes through `parse_checksummed`.
+#[cfg(feature = "serde")]
+pub mod checksum_serde {
+    use super::{format_address, parse_checksummed, Address, AddressCase, AddressFormat};
+    use serde::de::Error as SerdeError;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(address: &Address, serializer: S) -> Result<S::Ok, S::Error> {
+        let opts = AddressFormat {
+            prefix: true,
+            case: AddressCase::Checksum,
+        };
+        serializer.serialize_str(&format_address(address, opts))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Address, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        parse_checksummed(&s).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{pubkey_to_address, random_pubkey};
+    use super::{
+        address_leading_zero_bits, addresses_ct_eq, format_address, Address, AddressCase,
+        AddressFormat,
+    };
+    use cita_types::H160;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_address_conversions() {
+        let pubkey = random_pubkey();
+        assert_eq!(Address::from(pubkey), pubkey_to_address(&pubkey));
+        assert_eq!(Address::from(&pubkey), pubkey_to_address(&pubkey));
+
+        let hash = H160::repeat_byte(0x11);
+        let address = Address::from(hash);
+        assert_eq!(H160::from(address), hash);
+        assert_eq!(address.as_bytes(), hash.as_bytes());
+        assert_eq!(
+            Address::from_str("0x1111111111111111111111111111111111111111").unwrap(),
+            address
+        );
+        assert!(Address::from_str("0x11").is_err());
+        assert!(Address::zero().is_zero());
+    }
+
+    #[test]
+    fn test_format_address() {
+        let addr = Address::from_str("5aaeb6053f3e94c9b9a09f33669435e7ef1beaed").unwrap();
+        let cases = [
+            (
+                true,
+                AddressCase::Lower,
+                "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed",
+            ),
+            (
+                false,
+                AddressCase::Lower,
+                "5aaeb6053f3e94c9b9a09f33669435e7ef1beaed",
+            ),
+            (
+                true,
+                AddressCase::Upper,
+                "0x5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED",
+            ),
+            (
+                false,
+                AddressCase::Upper,
+                "5AAEB6053F3E94C9B9A09F33669435E7EF1BEAED",
+            ),
+            (
+                true,
+                AddressCase::Checksum,
+                "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            ),
+            (
+                false,
+                AddressCase::Checksum,
+                "5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            ),
+        ];
+        for (prefix, case, expected) in cases.iter() {
+            let opts = AddressFormat {
+                prefix: *prefix,
+                case: *case,
+            };
+            assert_eq!(format_address(&addr, opts), *expected);
+        }
+        assert_eq!(
+            format_address(&addr, AddressFormat::default()),
+            "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed"
+        );
+    }
+
+    #[test]
+    fn test_checksum_vectors() {
+        let opts = AddressFormat {
+            prefix: true,
+            case: AddressCase::Checksum,
+        };
+        for expected in [
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ]
+        .iter()
+        {
+            let addr = Address::from_str(&expected[2..].to_lowercase()).unwrap();
+            assert_eq!(&format_address(&addr, opts), expected);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_checksum_serde() {
+        use super::{checksum_serde, parse_checksummed};
+
+        let checksummed = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        let address = Address::from_str(&checksummed.to_lowercase()).unwrap();
+
+        let mut json = Vec::new();
+        checksum_serde::serialize(&address, &mut serde_json::Serializer::new(&mut json)).unwrap();
+        assert_eq!(json, format!("\"{}\"", checksummed).into_bytes());
+
+        let parse = |s: &str| {
+            checksum_serde::deserialize(&mut serde_json::Deserializer::from_str(&format!(
+                "\"{}\"",
+                s
+            )))
+        };
+        assert_eq!(parse(checksummed).unwrap(), address);
+        assert_eq!(parse(&checksummed.to_lowercase()).unwrap(), address);
+        assert!(parse("0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed").is_err());
+        assert!(parse_checksummed(&checksummed[2..]).is_err());
+    }
+
+    #[test]
+    fn test_parse_checksummed_prefix() {
+        use super::parse_checksummed;
+
+        let lower = "5aaeb6053f3e94c9b9a09f33669435e7ef1beaed";
+        assert_eq!(
+            parse_checksummed(&format!("0x{}", lower)).unwrap(),
+            Address::from_str(lower).unwrap()
+        );
+        assert!(parse_checksummed(&format!("0x0x{}", lower)).is_err());
+    }
+
+    #[test]
+    fn test_address_leading_zero_bits() {
+        assert_eq!(address_leading_zero_bits(&Address::zero()), 160);
+        let mut bytes = [0xffu8; 20];
+        assert_eq!(address_leading_zero_bits(&Address::from(bytes)), 0);
+        bytes[0] = 0;
+        bytes[1] = 0x01;
+        assert_eq!(address_leading_zero_bits(&Address::from(bytes)), 15);
+        let mut last = [0u8; 20];
+        last[19] = 0x80;
+        assert_eq!(address_leading_zero_bits(&Address::from(last)), 152);
+    }
+
+    #[test]
+    fn test_addresses_ct_eq() {
+        let a = Address::from(H160::repeat_byte(0x11));
+        let mut bytes = [0x11u8; 20];
+        bytes[19] = 0x12;
+        let b = Address::from(bytes);
+        for (x, y) in [(a, a), (a, b), (b, b), (Address::zero(), a)].iter() {
+            assert_eq!(addresses_ct_eq(x, y), x == y);
+        }
+    }
+}
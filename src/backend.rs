@@ -0,0 +1,408 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "secp256k1")]
+use super::signature::{from_secp_pubkey, to_recoverable, to_secp_pubkey};
+#[cfg(feature = "secp256k1")]
+use super::SECP256K1;
+use super::{Error, Message, PrivKey, PubKey, Signature};
+#[cfg(feature = "libsecp256k1-backend")]
+use hmac::{Hmac, Mac};
+#[cfg(feature = "secp256k1")]
+use secp256k1::{Error as SecpError, Message as SecpMessage, PublicKey, SecretKey};
+#[cfg(feature = "libsecp256k1-backend")]
+use sha2::Sha256;
+
+/// The ECDSA primitives a secp256k1 implementation has to provide.
+///
+/// Signatures use the crate layout, r || s || v, with `v` as the raw recovery id.
+pub trait Backend {
+    /// Public key in the backend's parsed form, reusable across verifications.
+    type PublicKey;
+    /// Digest in the backend's parsed form, reusable across signatures over it.
+    type ParsedMessage;
+
+    fn sign(privkey: &PrivKey, message: &Message) -> Result<Signature, Error>;
+    /// Sign with `aux_rand` mixed into the RFC 6979 nonce derivation.
+    fn sign_hedged(
+        privkey: &PrivKey,
+        message: &Message,
+        aux_rand: &[u8; 32],
+    ) -> Result<Signature, Error>;
+    /// Derive the public key, erroring on 0 and scalars not below the order.
+    fn derive_pubkey(privkey: &PrivKey) -> Result<PubKey, Error>;
+    fn parse_pubkey(pubkey: &PubKey) -> Result<Self::PublicKey, Error>;
+    /// Parse a 33-byte compressed public key.
+    fn decompress(bytes: &[u8; 33]) -> Result<PubKey, Error>;
+    fn parse_message(message: &Message) -> Result<Self::ParsedMessage, Error>;
+    fn verify_prepared(
+        pubkey: &Self::PublicKey,
+        signature: &Signature,
+        message: &Self::ParsedMessage,
+    ) -> Result<bool, Error>;
+    fn recover_prepared(
+        signature: &Signature,
+        message: &Self::ParsedMessage,
+    ) -> Result<PubKey, Error>;
+
+    fn verify_parsed(
+        pubkey: &Self::PublicKey,
+        signature: &Signature,
+        message: &Message,
+    ) -> Result<bool, Error> {
+        Self::verify_prepared(pubkey, signature, &Self::parse_message(message)?)
+    }
+
+    fn recover(signature: &Signature, message: &Message) -> Result<PubKey, Error> {
+        Self::recover_prepared(signature, &Self::parse_message(message)?)
+    }
+
+    fn verify(pubkey: &PubKey, signature: &Signature, message: &Message) -> Result<bool, Error> {
+        Self::verify_parsed(&Self::parse_pubkey(pubkey)?, signature, message)
+    }
+}
+
+/// Backend using the C libsecp256k1 through the `secp256k1` crate.
+#[cfg(feature = "secp256k1")]
+pub struct Secp256k1Backend;
+
+/// Backend using the pure Rust `libsecp256k1` crate.
+///
+/// Signatures are bit-identical to `Secp256k1Backend` except for digests not
+/// below the group order: `libsecp256k1` reduces the digest before deriving the
+/// nonce, so both signatures are valid but differ. Hedged signatures derive the
+/// nonce here, from the digest as is, and always match.
+///
+/// Built without the `secp256k1` feature no C code is compiled in; the
+/// functions tied to C types (`to_secp_pubkey`, `verify_with_secp_pubkey`,
+/// `ecdh`, `recover_privkey_from_reuse` and the like) are left out then.
+#[cfg(feature = "libsecp256k1-backend")]
+pub struct LibSecp256k1Backend;
+
+/// Backend the crate level functions go through.
+#[cfg(all(feature = "secp256k1", not(feature = "libsecp256k1-backend")))]
+pub type DefaultBackend = Secp256k1Backend;
+
+/// Backend the crate level functions go through.
+#[cfg(feature = "libsecp256k1-backend")]
+pub type DefaultBackend = LibSecp256k1Backend;
+
+#[cfg(feature = "secp256k1")]
+impl Backend for Secp256k1Backend {
+    type PublicKey = secp256k1::PublicKey;
+    type ParsedMessage = SecpMessage;
+
+    fn sign(privkey: &PrivKey, message: &Message) -> Result<Signature, Error> {
+        let context = &SECP256K1;
+        // checked parse, rejects 0 and scalars not below the order.
+        let sec = SecretKey::from_slice(&privkey.0)?;
+        let s = context.sign_ecdsa_recoverable(&SecpMessage::from_slice(message.as_bytes())?, &sec);
+        let (rec_id, data) = s.serialize_compact();
+        let mut data_arr = [0; 65];
+
+        // no need to check if s is low, it always is
+        data_arr[0..64].copy_from_slice(&data[0..64]);
+        data_arr[64] = rec_id.to_i32() as u8;
+        Ok(Signature(data_arr))
+    }
+
+    fn sign_hedged(
+        privkey: &PrivKey,
+        message: &Message,
+        aux_rand: &[u8; 32],
+    ) -> Result<Signature, Error> {
+        let sec = SecretKey::from_slice(&privkey.0)?;
+        let rsig = SECP256K1.sign_ecdsa_recoverable_with_noncedata(
+            &SecpMessage::from_slice(message.as_bytes())?,
+            &sec,
+            aux_rand,
+        );
+        let (rec_id, rs) = rsig.serialize_compact();
+        Signature::from_compact(&rs, rec_id.to_i32() as u8)
+    }
+
+    fn derive_pubkey(privkey: &PrivKey) -> Result<PubKey, Error> {
+        let sec = SecretKey::from_slice(&privkey.0)?;
+        Ok(from_secp_pubkey(&PublicKey::from_secret_key(
+            &SECP256K1, &sec,
+        )))
+    }
+
+    fn parse_pubkey(pubkey: &PubKey) -> Result<Self::PublicKey, Error> {
+        to_secp_pubkey(pubkey)
+    }
+
+    fn decompress(bytes: &[u8; 33]) -> Result<PubKey, Error> {
+        Ok(from_secp_pubkey(&PublicKey::from_slice(bytes)?))
+    }
+
+    fn parse_message(message: &Message) -> Result<Self::ParsedMessage, Error> {
+        Ok(SecpMessage::from_slice(message.as_bytes())?)
+    }
+
+    fn verify_prepared(
+        pubkey: &Self::PublicKey,
+        signature: &Signature,
+        message: &Self::ParsedMessage,
+    ) -> Result<bool, Error> {
+        let context = &SECP256K1;
+        let sig = to_recoverable(signature)?.to_standard();
+        match context.verify_ecdsa(message, &sig, pubkey) {
+            Ok(_) => Ok(true),
+            Err(SecpError::IncorrectSignature) => Ok(false),
+            Err(x) => Err(Error::from(x)),
+        }
+    }
+
+    fn recover_prepared(
+        signature: &Signature,
+        message: &Self::ParsedMessage,
+    ) -> Result<PubKey, Error> {
+        let context = &SECP256K1;
+        let rsig = to_recoverable(signature)?;
+        Ok(from_secp_pubkey(&context.recover_ecdsa(message, &rsig)?))
+    }
+}
+
+/// HMAC-SHA256 DRBG of RFC 6979, seeded like the C libsecp256k1 seeds it:
+/// private key, digest and extra data back to back.
+#[cfg(feature = "libsecp256k1-backend")]
+struct Rfc6979 {
+    k: [u8; 32],
+    v: [u8; 32],
+    retry: bool,
+}
+
+#[cfg(feature = "libsecp256k1-backend")]
+impl Rfc6979 {
+    fn new(seed: &[&[u8]]) -> Rfc6979 {
+        let mut drbg = Rfc6979 {
+            k: [0; 32],
+            v: [1; 32],
+            retry: false,
+        };
+        for tag in [0u8, 1].iter() {
+            let mut data = vec![&drbg.v[..], std::slice::from_ref(tag)];
+            data.extend_from_slice(seed);
+            drbg.k = drbg.hmac(&data);
+            drbg.v = drbg.hmac(&[&drbg.v[..]]);
+        }
+        drbg
+    }
+
+    fn hmac(&self, data: &[&[u8]]) -> [u8; 32] {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.k).expect("any key length");
+        for part in data {
+            mac.update(part);
+        }
+        mac.finalize().into_bytes().into()
+    }
+
+    fn generate(&mut self) -> [u8; 32] {
+        if self.retry {
+            self.k = self.hmac(&[&self.v[..], &[0u8][..]]);
+            self.v = self.hmac(&[&self.v[..]]);
+        }
+        self.v = self.hmac(&[&self.v[..]]);
+        self.retry = true;
+        self.v
+    }
+}
+
+#[cfg(feature = "libsecp256k1-backend")]
+impl LibSecp256k1Backend {
+    fn parse(
+        signature: &Signature,
+    ) -> Result<(libsecp256k1::Signature, libsecp256k1::RecoveryId), Error> {
+        let rec_id = libsecp256k1::RecoveryId::parse(signature.v())?;
+        let sig = libsecp256k1::Signature::parse_standard_slice(&signature.0[0..64])?;
+        Ok((sig, rec_id))
+    }
+}
+
+#[cfg(feature = "libsecp256k1-backend")]
+impl Backend for LibSecp256k1Backend {
+    type PublicKey = libsecp256k1::PublicKey;
+    /// The digest reduced to a scalar.
+    type ParsedMessage = libsecp256k1::Message;
+
+    fn sign(privkey: &PrivKey, message: &Message) -> Result<Signature, Error> {
+        let sec = libsecp256k1::SecretKey::parse(&privkey.0)?;
+        let (sig, rec_id) = libsecp256k1::sign(
+            &libsecp256k1::Message::parse(message.as_fixed_bytes()),
+            &sec,
+        );
+        let mut data_arr = [0; 65];
+        data_arr[0..64].copy_from_slice(&sig.serialize());
+        data_arr[64] = rec_id.serialize();
+        Ok(Signature(data_arr))
+    }
+
+    fn sign_hedged(
+        privkey: &PrivKey,
+        message: &Message,
+        aux_rand: &[u8; 32],
+    ) -> Result<Signature, Error> {
+        use libsecp256k1::curve::Scalar;
+
+        // rejects 0 and scalars not below the order, so set_b32 can not overflow
+        libsecp256k1::SecretKey::parse(&privkey.0)?;
+        let mut seckey = Scalar::default();
+        let _ = seckey.set_b32(&privkey.0);
+        let mut digest = Scalar::default();
+        let _ = digest.set_b32(message.as_fixed_bytes());
+
+        let mut drbg = Rfc6979::new(&[&privkey.0, message.as_fixed_bytes(), aux_rand]);
+        let mut nonce = Scalar::default();
+        let (r, s, recid) = loop {
+            let overflow = bool::from(nonce.set_b32(&drbg.generate()));
+            if !overflow && !nonce.is_zero() {
+                if let Ok(signed) =
+                    libsecp256k1::ECMULT_GEN_CONTEXT.sign_raw(&seckey, &digest, &nonce)
+                {
+                    break signed;
+                }
+            }
+        };
+        seckey.clear();
+        nonce.clear();
+
+        let mut data_arr = [0; 65];
+        data_arr[0..32].copy_from_slice(&r.b32());
+        data_arr[32..64].copy_from_slice(&s.b32());
+        data_arr[64] = recid;
+        Ok(Signature(data_arr))
+    }
+
+    fn derive_pubkey(privkey: &PrivKey) -> Result<PubKey, Error> {
+        let sec = libsecp256k1::SecretKey::parse(&privkey.0)?;
+        let publ = libsecp256k1::PublicKey::from_secret_key(&sec);
+        Ok(PubKey::from_slice(&publ.serialize()[1..65]))
+    }
+
+    fn parse_pubkey(pubkey: &PubKey) -> Result<Self::PublicKey, Error> {
+        let mut pdata = [4u8; 65];
+        pdata[1..65].copy_from_slice(pubkey.as_bytes());
+        Ok(libsecp256k1::PublicKey::parse(&pdata)?)
+    }
+
+    fn decompress(bytes: &[u8; 33]) -> Result<PubKey, Error> {
+        let publ = libsecp256k1::PublicKey::parse_compressed(bytes)?;
+        Ok(PubKey::from_slice(&publ.serialize()[1..65]))
+    }
+
+    fn parse_message(message: &Message) -> Result<Self::ParsedMessage, Error> {
+        Ok(libsecp256k1::Message::parse(message.as_fixed_bytes()))
+    }
+
+    fn verify_prepared(
+        pubkey: &Self::PublicKey,
+        signature: &Signature,
+        message: &Self::ParsedMessage,
+    ) -> Result<bool, Error> {
+        let (sig, _) = Self::parse(signature)?;
+        // The C backend rejects high s, libsecp256k1::verify does not, so match it here.
+        if sig.s.is_high() {
+            return Ok(false);
+        }
+        Ok(libsecp256k1::verify(message, &sig, pubkey))
+    }
+
+    fn recover_prepared(
+        signature: &Signature,
+        message: &Self::ParsedMessage,
+    ) -> Result<PubKey, Error> {
+        let (sig, rec_id) = Self::parse(signature)?;
+        let publ = libsecp256k1::recover(message, &sig, &rec_id)?;
+        Ok(PubKey::from_slice(&publ.serialize()[1..65]))
+    }
+}
+
+#[cfg(all(test, feature = "libsecp256k1-backend", feature = "secp256k1"))]
+mod tests {
+    use super::{Backend, LibSecp256k1Backend, Secp256k1Backend};
+    use crate::{Message, PrivKey};
+    use cita_types::H256;
+    use std::str::FromStr;
+
+    const FIXTURES: [(&str, &str); 3] = [
+        (
+            "80762b900f072d199e35ea9b1ee0e2e631a87762f8855b32d4ec13e37a3a65c1",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+        ),
+        (
+            "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318",
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470",
+        ),
+        (
+            "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364140",
+            "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364140",
+        ),
+    ];
+
+    #[test]
+    fn test_backends_agree() {
+        for (privkey, message) in FIXTURES.iter() {
+            let privkey = PrivKey::from(H256::from_str(privkey).unwrap());
+            let message = Message::from_digest(H256::from_str(message).unwrap());
+
+            let c_sig = Secp256k1Backend::sign(&privkey, &message).unwrap();
+            let rust_sig = LibSecp256k1Backend::sign(&privkey, &message).unwrap();
+            assert_eq!(c_sig, rust_sig);
+
+            let c_pubkey = Secp256k1Backend::recover(&c_sig, &message).unwrap();
+            let rust_pubkey = LibSecp256k1Backend::recover(&c_sig, &message).unwrap();
+            assert_eq!(c_pubkey, rust_pubkey);
+
+            assert!(Secp256k1Backend::verify(&c_pubkey, &rust_sig, &message).unwrap());
+            assert!(LibSecp256k1Backend::verify(&c_pubkey, &c_sig, &message).unwrap());
+
+            assert_eq!(
+                LibSecp256k1Backend::derive_pubkey(&privkey).unwrap(),
+                c_pubkey
+            );
+        }
+    }
+
+    #[test]
+    fn test_backends_agree_hedged() {
+        let digests = [H256::from_low_u64_be(1), H256::repeat_byte(0xff)];
+        for (i, digest) in digests.iter().enumerate() {
+            let privkey = PrivKey::from(H256::from_low_u64_be(i as u64 + 7));
+            let message = Message::from_digest(*digest);
+            for aux_rand in [[0u8; 32], [0x5a; 32]].iter() {
+                assert_eq!(
+                    LibSecp256k1Backend::sign_hedged(&privkey, &message, aux_rand).unwrap(),
+                    Secp256k1Backend::sign_hedged(&privkey, &message, aux_rand).unwrap()
+                );
+            }
+        }
+        assert!(
+            LibSecp256k1Backend::sign_hedged(&PrivKey::zero(), &Message::hash(""), &[0u8; 32])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_backends_digest_above_order() {
+        let privkey = PrivKey::from(H256::from_low_u64_be(1));
+        let message = Message::from_digest(H256::repeat_byte(0xff));
+
+        let c_sig = Secp256k1Backend::sign(&privkey, &message).unwrap();
+        let rust_sig = LibSecp256k1Backend::sign(&privkey, &message).unwrap();
+        let pubkey = Secp256k1Backend::recover(&c_sig, &message).unwrap();
+        assert!(Secp256k1Backend::verify(&pubkey, &rust_sig, &message).unwrap());
+        assert!(LibSecp256k1Backend::verify(&pubkey, &c_sig, &message).unwrap());
+    }
+}
@@ -0,0 +1,47 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{to_secp_pubkey, Error, PrivKey, PubKey};
+use secp256k1::ecdh::SharedSecret;
+use secp256k1::SecretKey;
+
+/// Shared secret between our private key and a peer's public key.
+///
+/// This is libsecp256k1's default ECDH output, the SHA-256 of the compressed
+/// shared point, not the raw x coordinate some other protocols use. Only
+/// available with the `secp256k1` feature.
+pub fn ecdh(privkey: &PrivKey, pubkey: &PubKey) -> Result<[u8; 32], Error> {
+    let secret = SecretKey::from_slice(&privkey.0)?;
+    let point = to_secp_pubkey(pubkey)?;
+    Ok(SharedSecret::new(&point, &secret).secret_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{KeyPair, PrivKey, PubKey};
+    use super::ecdh;
+    use cita_crypto_trait::CreateKey;
+
+    #[test]
+    fn test_ecdh() {
+        let a = KeyPair::gen_keypair();
+        let b = KeyPair::gen_keypair();
+        let shared = ecdh(a.privkey(), b.pubkey()).unwrap();
+        assert_eq!(shared, ecdh(b.privkey(), a.pubkey()).unwrap());
+        assert_ne!(shared, ecdh(a.privkey(), a.pubkey()).unwrap());
+
+        assert!(ecdh(&PrivKey::zero(), b.pubkey()).is_err());
+        assert!(ecdh(a.privkey(), &PubKey::zero()).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hex and base64 helpers, so the crate does not depend on `rustc-serialize`.
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+/// Lowercase hex without prefix, the same output as `rustc_serialize`'s `to_hex`.
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        hex.push(HEX_DIGITS[usize::from(b >> 4)] as char);
+        hex.push(HEX_DIGITS[usize::from(b & 0x0f)] as char);
+    }
+    hex
+}
+
+fn hex_value(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Decode hex digits of either case, without prefix.
+pub(crate) fn from_hex(s: &str) -> Option<Vec<u8>> {
+    let pairs = s.as_bytes().chunks_exact(2);
+    if !pairs.remainder().is_empty() {
+        return None;
+    }
+    pairs
+        .map(|pair| Some(hex_value(pair[0])? << 4 | hex_value(pair[1])?))
+        .collect()
+}
+
+fn base64_value(c: u8) -> Option<u32> {
+    let v = match c {
+        b'A'..=b'Z' => c - b'A',
+        b'a'..=b'z' => c - b'a' + 26,
+        b'0'..=b'9' => c - b'0' + 52,
+        b'+' | b'-' => 62,
+        b'/' | b'_' => 63,
+        _ => return None,
+    };
+    Some(u32::from(v))
+}
+
+/// Decode standard or URL-safe base64, padding optional.
+pub(crate) fn from_base64(s: &str) -> Option<Vec<u8>> {
+    let data = s.trim_end_matches('=').as_bytes();
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    for chunk in data.chunks(4) {
+        let mut buf = 0u32;
+        for c in chunk {
+            buf = buf << 6 | base64_value(*c)?;
+        }
+        match chunk.len() {
+            4 => out.extend_from_slice(&buf.to_be_bytes()[1..4]),
+            3 => out.extend_from_slice(&(buf << 6).to_be_bytes()[1..3]),
+            2 => out.push((buf >> 4) as u8),
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_base64, from_hex, to_hex};
+    use rustc_serialize::base64::{ToBase64, STANDARD, URL_SAFE};
+    use rustc_serialize::hex::ToHex;
+
+    #[test]
+    fn test_matches_rustc_serialize() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        for len in 0..bytes.len() {
+            let input = &bytes[bytes.len() - len..];
+            assert_eq!(to_hex(input), input.to_hex());
+            assert_eq!(from_hex(&input.to_hex()).unwrap(), input);
+            assert_eq!(from_hex(&input.to_hex().to_uppercase()).unwrap(), input);
+            assert_eq!(from_base64(&input.to_base64(STANDARD)).unwrap(), input);
+            assert_eq!(from_base64(&input.to_base64(URL_SAFE)).unwrap(), input);
+        }
+        assert!(from_hex("abc").is_none());
+        assert!(from_hex("zz").is_none());
+        assert!(from_base64("a").is_none());
+        assert!(from_base64("a b=").is_none());
+    }
+}
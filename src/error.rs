@@ -21,25 +21,52 @@ pub enum Error {
     InvalidAddress,
     InvalidSignature,
     InvalidMessage,
+    ROutOfRange,
+    SOutOfRange,
+    VOutOfRange,
+    UnsupportedVersion(u8),
+    Unrecoverable,
+    /// Decryption key derived from the password does not match.
+    InvalidPassword,
     Io(::std::io::Error),
     Unexpected(String),
+    /// Error raised inside the named operation.
+    Context(&'static str, Box<Error>),
 }
 
-impl fmt::Display for Error {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let msg = match *self {
+impl Error {
+    /// Attach the name of the failing operation.
+    pub(crate) fn context(self, origin: &'static str) -> Error {
+        Error::Context(origin, Box::new(self))
+    }
+
+    fn reason(&self) -> String {
+        match *self {
             Error::InvalidPrivKey => "Invalid secret".into(),
             Error::InvalidPubKey => "Invalid public".into(),
             Error::InvalidAddress => "Invalid address".into(),
             Error::InvalidSignature => "Invalid EC signature".into(),
             Error::InvalidMessage => "Invalid AES message".into(),
+            Error::ROutOfRange => "Signature r out of range".into(),
+            Error::SOutOfRange => "Signature s out of range".into(),
+            Error::VOutOfRange => "Signature v out of range".into(),
+            Error::Unrecoverable => "No public key recoverable from signature".into(),
+            Error::InvalidPassword => "Invalid password".into(),
+            Error::UnsupportedVersion(v) => format!("Unsupported format version {}", v),
             Error::Io(ref err) => format!("I/O error: {}", err),
             Error::Unexpected(ref s) => s.clone(),
-        };
-        f.write_fmt(format_args!("Crypto error ({})", msg))
+            Error::Context(origin, ref err) => format!("{}: {}", origin, err.reason()),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_fmt(format_args!("Crypto error ({})", self.reason()))
     }
 }
 
+#[cfg(feature = "secp256k1")]
 impl From<::secp256k1::Error> for Error {
     fn from(e: ::secp256k1::Error) -> Error {
         match e {
@@ -51,6 +78,18 @@ impl From<::secp256k1::Error> for Error {
     }
 }
 
+#[cfg(feature = "libsecp256k1-backend")]
+impl From<::libsecp256k1::Error> for Error {
+    fn from(e: ::libsecp256k1::Error) -> Error {
+        match e {
+            ::libsecp256k1::Error::InvalidMessage => Error::InvalidMessage,
+            ::libsecp256k1::Error::InvalidPublicKey => Error::InvalidPubKey,
+            ::libsecp256k1::Error::InvalidSecretKey => Error::InvalidPrivKey,
+            _ => Error::InvalidSignature,
+        }
+    }
+}
+
 impl From<::std::io::Error> for Error {
     fn from(err: ::std::io::Error) -> Error {
         Error::Io(err)
@@ -0,0 +1,74 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{sign, Error, ExtendedSignature, Message, PrivKey};
+use cita_types::H256;
+use tiny_keccak::{Hasher, Keccak};
+
+/// Keccak-256, the hash Ethereum uses whichever hash feature this crate is built with.
+pub fn keccak256(data: &[u8]) -> H256 {
+    let mut hash = H256::zero();
+    let mut keccak = Keccak::v256();
+    keccak.update(data);
+    keccak.finalize(&mut hash.0);
+    hash
+}
+
+/// Sign an Ethereum legacy transaction with an EIP-155 `v`.
+///
+/// `tx_rlp` is the RLP of the unsigned transaction, which for EIP-155 already
+/// ends with `chain_id, 0, 0`; it is hashed with keccak256 before signing.
+pub fn sign_transaction(
+    privkey: &PrivKey,
+    tx_rlp: &[u8],
+    chain_id: u64,
+) -> Result<ExtendedSignature, Error> {
+    let message = Message::from_digest(keccak256(tx_rlp));
+    ExtendedSignature::from_eip155(&sign(privkey, &message)?, chain_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{recover, KeyPair, Message};
+    use super::{keccak256, sign_transaction};
+    use cita_crypto_trait::CreateKey;
+    use cita_types::H256;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_keccak256() {
+        assert_eq!(
+            keccak256(&[]),
+            H256::from_str("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sign_transaction() {
+        let keypair = KeyPair::gen_keypair();
+        let tx_rlp = [0xc9u8, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01, 0x80, 0x80];
+        let sig = sign_transaction(keypair.privkey(), &tx_rlp, 1).unwrap();
+
+        let parity = sig.recovery_id().unwrap();
+        assert_eq!(sig.v(), 37 + u64::from(parity));
+        assert_eq!(sig.chain_id(), Some(1));
+
+        let compact = sig.to_signature().unwrap();
+        assert_eq!(
+            keypair.pubkey(),
+            &recover(&compact, &Message::from_digest(keccak256(&tx_rlp))).unwrap()
+        );
+    }
+}
@@ -0,0 +1,237 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{recover, Error, Message, PubKey, Signature};
+use cita_types::H256;
+use std::convert::TryFrom;
+
+/// Offset added to `v` by EIP-155, on top of `chain_id * 2`.
+const EIP155_V_OFFSET: u64 = 35;
+/// Offset used by the legacy (pre EIP-155) encoding of `v`.
+const LEGACY_V_OFFSET: u64 = 27;
+
+/// How a signature's `v` encodes the recovery id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryConvention {
+    /// `v` is the recovery id itself, 0 or 1.
+    Raw,
+    /// Pre EIP-155 Ethereum, 27 or 28.
+    Legacy,
+    /// EIP-155 with the given chain id, `chain_id * 2 + 35` or `+ 36`.
+    Eip155(u64),
+}
+
+impl RecoveryConvention {
+    /// Get the raw recovery id from `v`.
+    ///
+    /// A `v` this convention does not produce, e.g. 27 for `Raw` or another
+    /// chain id for `Eip155`, gives `VOutOfRange`.
+    pub fn recovery_id(&self, v: u64) -> Result<u8, Error> {
+        let offset = match *self {
+            RecoveryConvention::Raw => 0,
+            RecoveryConvention::Legacy => LEGACY_V_OFFSET,
+            RecoveryConvention::Eip155(chain_id) => chain_id
+                .checked_mul(2)
+                .and_then(|v| v.checked_add(EIP155_V_OFFSET))
+                .ok_or(Error::VOutOfRange)?,
+        };
+        match v.checked_sub(offset) {
+            Some(id) if id <= 1 => Ok(id as u8),
+            _ => Err(Error::VOutOfRange),
+        }
+    }
+}
+
+/// Recover the public key, with `v` read according to `convention`.
+pub fn recover_with_convention(
+    signature: &ExtendedSignature,
+    convention: RecoveryConvention,
+    message: &Message,
+) -> Result<PubKey, Error> {
+    let recovery_id = convention.recovery_id(signature.v)?;
+    recover(
+        &Signature::from_rsv(&signature.r, &signature.s, recovery_id),
+        message,
+    )
+}
+
+/// Signature whose recovery field is wide enough for EIP-155 `v` values.
+///
+/// `Signature` keeps `v` in a single byte, which is enough for the raw
+/// recovery id but not for `recovery_id + chain_id * 2 + 35`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExtendedSignature {
+    r: H256,
+    s: H256,
+    v: u64,
+}
+
+impl ExtendedSignature {
+    /// Create an extended signature from its components.
+    pub fn new(r: H256, s: H256, v: u64) -> ExtendedSignature {
+        ExtendedSignature { r, s, v }
+    }
+
+    /// Create an EIP-155 signature from a canonical signature and a chain id.
+    pub fn from_eip155(sig: &Signature, chain_id: u64) -> Result<ExtendedSignature, Error> {
+        if sig.v() > 1 {
+            return Err(Error::InvalidSignature);
+        }
+        let v = chain_id
+            .checked_mul(2)
+            .and_then(|v| v.checked_add(EIP155_V_OFFSET + u64::from(sig.v())))
+            .ok_or(Error::InvalidSignature)?;
+        Ok(ExtendedSignature {
+            r: H256::from_slice(sig.r()),
+            s: H256::from_slice(sig.s()),
+            v,
+        })
+    }
+
+    /// Get the 'r' portion of the data.
+    pub fn r(&self) -> &H256 {
+        &self.r
+    }
+
+    /// Get the 's' portion of the data.
+    pub fn s(&self) -> &H256 {
+        &self.s
+    }
+
+    /// Get the full width recovery field.
+    pub fn v(&self) -> u64 {
+        self.v
+    }
+
+    /// Get the chain id encoded in `v`, if it uses the EIP-155 encoding.
+    pub fn chain_id(&self) -> Option<u64> {
+        if self.v >= EIP155_V_OFFSET {
+            Some((self.v - EIP155_V_OFFSET) / 2)
+        } else {
+            None
+        }
+    }
+
+    /// Get the raw recovery id, whichever encoding `v` uses.
+    pub fn recovery_id(&self) -> Result<u8, Error> {
+        let convention = match (self.v, self.chain_id()) {
+            (0..=1, _) => RecoveryConvention::Raw,
+            (27..=28, _) => RecoveryConvention::Legacy,
+            (_, Some(chain_id)) => RecoveryConvention::Eip155(chain_id),
+            _ => return Err(Error::InvalidSignature),
+        };
+        convention.recovery_id(self.v)
+    }
+
+    /// Convert into the canonical compact form, with `v` as the raw recovery id.
+    pub fn to_signature(&self) -> Result<Signature, Error> {
+        Ok(Signature::from_rsv(&self.r, &self.s, self.recovery_id()?))
+    }
+}
+
+impl From<Signature> for ExtendedSignature {
+    fn from(sig: Signature) -> Self {
+        ExtendedSignature::from(&sig)
+    }
+}
+
+impl<'a> From<&'a Signature> for ExtendedSignature {
+    fn from(sig: &'a Signature) -> Self {
+        ExtendedSignature {
+            r: H256::from_slice(sig.r()),
+            s: H256::from_slice(sig.s()),
+            v: u64::from(sig.v()),
+        }
+    }
+}
+
+/// Keeps `v` as is, so it fails when `v` does not fit the compact layout.
+impl TryFrom<ExtendedSignature> for Signature {
+    type Error = Error;
+
+    fn try_from(sig: ExtendedSignature) -> Result<Self, Self::Error> {
+        let v = u8::try_from(sig.v).map_err(|_| Error::InvalidSignature)?;
+        Ok(Signature::from_rsv(&sig.r, &sig.s, v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Error, KeyPair, Message};
+    use super::{recover_with_convention, ExtendedSignature, RecoveryConvention, Signature};
+    use cita_crypto_trait::{CreateKey, Sign};
+    use std::convert::TryFrom;
+
+    #[test]
+    fn test_eip155_roundtrip() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+
+        let extended = ExtendedSignature::from_eip155(&sig, 1_000_000).unwrap();
+        assert_eq!(extended.v(), 2_000_035 + u64::from(sig.v()));
+        assert_eq!(extended.chain_id(), Some(1_000_000));
+        assert_eq!(extended.recovery_id().unwrap(), sig.v());
+        assert_eq!(extended.to_signature().unwrap(), sig);
+    }
+
+    #[test]
+    fn test_recover_with_convention() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        let with_v = |v: u64| ExtendedSignature::new(sig.r_array().into(), sig.s_array().into(), v);
+        let id = u64::from(sig.v());
+
+        let cases = [
+            (RecoveryConvention::Raw, id),
+            (RecoveryConvention::Legacy, 27 + id),
+            (RecoveryConvention::Eip155(1), 37 + id),
+        ];
+        for (convention, v) in &cases {
+            let pubkey = recover_with_convention(&with_v(*v), *convention, &message).unwrap();
+            assert_eq!(pubkey, *keypair.pubkey());
+        }
+
+        let mismatched = [
+            (RecoveryConvention::Raw, 27 + id),
+            (RecoveryConvention::Legacy, id),
+            (RecoveryConvention::Eip155(1), 27 + id),
+            (RecoveryConvention::Eip155(2), 37 + id),
+            (RecoveryConvention::Eip155(0), 37 + id),
+            (RecoveryConvention::Eip155(u64::MAX), 37 + id),
+        ];
+        for (convention, v) in &mismatched {
+            match recover_with_convention(&with_v(*v), *convention, &message) {
+                Err(Error::VOutOfRange) => {}
+                other => panic!("{:?} accepted v {}: {:?}", convention, v, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_compact_conversion() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+
+        let extended = ExtendedSignature::from(&sig);
+        assert_eq!(extended.chain_id(), None);
+        assert_eq!(Signature::try_from(extended).unwrap(), sig);
+
+        let wide = ExtendedSignature::from_eip155(&sig, 1_000_000).unwrap();
+        assert!(Signature::try_from(wide).is_err());
+        assert!(ExtendedSignature::from_eip155(&sig, u64::MAX).is_err());
+    }
+}
@@ -0,0 +1,94 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `arbitrary` support for structure-aware fuzzing.
+//!
+//! `PrivKey` and `PubKey` are aliases of `cita_types` hashes, so they get
+//! generator functions instead of `Arbitrary` impls.
+
+use super::signature::reduce_scalar;
+use super::{Address, KeyPair, PrivKey, PubKey, Signature};
+use arbitrary::{Arbitrary, Unstructured};
+use cita_crypto_trait::CreateKey;
+
+/// Generate a valid private key: the input reduced modulo the order, 0 mapped to 1.
+pub fn arbitrary_privkey(u: &mut Unstructured) -> arbitrary::Result<PrivKey> {
+    let (mut scalar, _) = reduce_scalar(&u.arbitrary()?);
+    if scalar == [0u8; 32] {
+        scalar[31] = 1;
+    }
+    Ok(PrivKey::from(scalar))
+}
+
+/// Generate a public key on the curve, derived from `arbitrary_privkey`.
+pub fn arbitrary_pubkey(u: &mut Unstructured) -> arbitrary::Result<PubKey> {
+    Ok(*KeyPair::arbitrary(u)?.pubkey())
+}
+
+impl<'a> Arbitrary<'a> for KeyPair {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(KeyPair::from_privkey(arbitrary_privkey(u)?).expect("valid scalar"))
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (32, Some(32))
+    }
+}
+
+/// Any 65 bytes, so out of range and unrecoverable signatures are generated too.
+impl<'a> Arbitrary<'a> for Signature {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Signature(u.arbitrary()?))
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (65, Some(65))
+    }
+}
+
+impl<'a> Arbitrary<'a> for Address {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Address::from(u.arbitrary::<[u8; 20]>()?))
+    }
+
+    fn size_hint(_depth: usize) -> (usize, Option<usize>) {
+        (20, Some(20))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{arbitrary_privkey, arbitrary_pubkey};
+    use crate::encoding::from_hex;
+    use crate::{privkey_from_be_bytes, validate_pubkey, Signature};
+    use arbitrary::{Arbitrary, Unstructured};
+
+    #[test]
+    fn test_arbitrary_privkey() {
+        // zero, the order itself and all ones are not valid scalars as is
+        let order =
+            from_hex("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141").unwrap();
+        let inputs = vec![vec![0u8; 32], order, vec![0xff; 32], (0..=255).collect()];
+        for data in &inputs {
+            let mut u = Unstructured::new(data);
+            let privkey = arbitrary_privkey(&mut u).unwrap();
+            assert!(privkey_from_be_bytes(&privkey.0).is_ok());
+        }
+
+        let data: Vec<u8> = (0..=255).rev().collect();
+        let mut u = Unstructured::new(&data);
+        assert!(validate_pubkey(&arbitrary_pubkey(&mut u).unwrap()).is_ok());
+        assert!(Signature::arbitrary(&mut u).is_ok());
+    }
+}
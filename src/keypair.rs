@@ -12,30 +12,174 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use super::{Address, Error, PrivKey, PubKey, SECP256K1};
+use super::encoding::to_hex;
+use super::signature::is_valid_privkey_scalar;
+use super::{Address, Backend, DefaultBackend, Error, PrivKey, PubKey};
 use cita_crypto_trait::CreateKey;
 use cita_types::H160;
 use hashable::Hashable;
-use rustc_serialize::hex::ToHex;
-use secp256k1::{PublicKey, SecretKey};
+use std::convert::TryFrom;
 use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
 
 pub fn pubkey_to_address(pubkey: &PubKey) -> Address {
-    H160::from(pubkey.crypt_hash())
+    Address::from(H160::from(pubkey.crypt_hash()))
+}
+
+/// Derive the address and prepend a network prefix, for chains that use one.
+pub fn pubkey_to_prefixed_address(pubkey: &PubKey, prefix: &[u8]) -> Vec<u8> {
+    let mut address = prefix.to_vec();
+    address.extend_from_slice(pubkey_to_address(pubkey).as_bytes());
+    address
+}
+
+/// Create a private key from its big-endian scalar bytes.
+///
+/// `PrivKey` always stores the scalar big-endian, the same way the SEC1 and
+/// Ethereum key formats do; the scalar must be non zero and below the order.
+pub fn privkey_from_be_bytes(bytes: &[u8; 32]) -> Result<PrivKey, Error> {
+    if !is_valid_privkey_scalar(bytes) {
+        return Err(Error::InvalidPrivKey);
+    }
+    Ok(PrivKey::from(*bytes))
+}
+
+/// PBKDF2-HMAC-SHA256 rounds used by `privkey_from_passphrase`.
+#[cfg(feature = "passphrase")]
+pub const PASSPHRASE_ITERATIONS: u32 = 600_000;
+
+/// Derive a private key from a passphrase with PBKDF2-HMAC-SHA256.
+///
+/// A derived value that is not a valid scalar, which has a chance of about
+/// 2^-128, is derived again with a counter byte appended to the salt.
+/// The key is only as strong as the passphrase, use a salt per wallet.
+#[cfg(feature = "passphrase")]
+pub fn privkey_from_passphrase(passphrase: &str, salt: &[u8]) -> Result<PrivKey, Error> {
+    privkey_from_passphrase_with(passphrase, salt, PASSPHRASE_ITERATIONS)
+}
+
+#[cfg(feature = "passphrase")]
+pub(crate) fn privkey_from_passphrase_with(
+    passphrase: &str,
+    salt: &[u8],
+    iterations: u32,
+) -> Result<PrivKey, Error> {
+    for counter in 0..=u8::MAX {
+        let mut salted = salt.to_vec();
+        if counter > 0 {
+            salted.push(counter);
+        }
+        let mut bytes = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<sha2::Sha256>(passphrase.as_bytes(), &salted, iterations, &mut bytes);
+        if let Ok(privkey) = privkey_from_be_bytes(&bytes) {
+            return Ok(privkey);
+        }
+    }
+    Err(Error::InvalidPrivKey)
+}
+
+/// Get the big-endian scalar bytes of a private key.
+pub fn privkey_to_be_bytes(privkey: &PrivKey) -> [u8; 32] {
+    privkey.0
+}
+
+/// Derive both the public key and the address of a private key.
+pub fn derive_identity(privkey: &PrivKey) -> Result<(PubKey, Address), Error> {
+    let keypair = KeyPair::from_privkey(*privkey)?;
+    Ok((keypair.pubkey, keypair.address()))
+}
+
+/// Check that the public key is a point on the curve.
+pub fn validate_pubkey(pubkey: &PubKey) -> Result<(), Error> {
+    DefaultBackend::parse_pubkey(pubkey).map(|_| ())
+}
+
+/// Parse a public key from 128 hex digits, with or without a `0x` prefix.
+///
+/// `PubKey` is an alias of `H512`, whose own `FromStr` accepts any 64 bytes
+/// and can not be replaced here; this one also checks the point is on the curve.
+pub fn pubkey_from_str(s: &str) -> Result<PubKey, Error> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    let pubkey = PubKey::from_str(s).map_err(|_| Error::InvalidPubKey)?;
+    validate_pubkey(&pubkey)?;
+    Ok(pubkey)
+}
+
+/// Random address for tests, any 20 bytes make a valid address.
+#[cfg(any(test, feature = "testing"))]
+pub fn random_address() -> Address {
+    Address::from(H160::random())
+}
+
+/// Random public key for tests, unlike `PubKey::random` it is on the curve.
+#[cfg(any(test, feature = "testing"))]
+pub fn random_pubkey() -> PubKey {
+    *KeyPair::gen_keypair().pubkey()
 }
 
 /// key pair
-#[derive(Default)]
 pub struct KeyPair {
     privkey: PrivKey,
     pubkey: PubKey,
+    /// Derived on first use; `OnceLock` rather than `OnceCell` keeps `KeyPair` `Sync`.
+    address: OnceLock<Address>,
 }
 
 impl fmt::Display for KeyPair {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        writeln!(f, "privkey:  {}", self.privkey.0.to_hex())?;
-        writeln!(f, "pubkey:  {}", self.pubkey.0.to_hex())?;
-        write!(f, "address:  {}", self.address().0.to_hex())
+        writeln!(f, "privkey:  {}", to_hex(&self.privkey.0))?;
+        writeln!(f, "pubkey:  {}", to_hex(&self.pubkey.0))?;
+        write!(f, "address:  {}", to_hex(self.address().as_bytes()))
+    }
+}
+
+/// Minimal Hamming weight of a hardened private key, a random scalar has ~128.
+const MIN_PRIVKEY_WEIGHT: u32 = 32;
+
+/// Check for pathological private keys: a single repeated byte or too few set bits.
+fn is_weak_privkey(privkey: &PrivKey) -> bool {
+    let weight: u32 = privkey.0.iter().map(|b| b.count_ones()).sum();
+    privkey.0.iter().all(|b| *b == privkey.0[0]) || weight < MIN_PRIVKEY_WEIGHT
+}
+
+/// Generates a fresh random key pair, it is *not* a zeroed key.
+impl Default for KeyPair {
+    fn default() -> Self {
+        KeyPair::gen_keypair()
+    }
+}
+
+impl KeyPair {
+    /// Generate a fresh random key pair, same as `gen_keypair`.
+    pub fn new() -> KeyPair {
+        KeyPair::gen_keypair()
+    }
+
+    /// Generate a key pair, regenerating while the private key looks pathological.
+    pub fn gen_keypair_hardened() -> KeyPair {
+        Self::gen_keypair_hardened_with(&mut rand::thread_rng())
+    }
+
+    fn gen_keypair_hardened_with<R: rand::Rng + ?Sized>(rng: &mut R) -> KeyPair {
+        loop {
+            let keypair = Self::gen_keypair_with(rng);
+            if !is_weak_privkey(&keypair.privkey) {
+                return keypair;
+            }
+        }
+    }
+
+    /// Draw 32 bytes at a time until they make a valid scalar.
+    fn gen_keypair_with<R: rand::Rng + ?Sized>(rng: &mut R) -> KeyPair {
+        let mut privkey = PrivKey::default();
+        loop {
+            rng.fill_bytes(&mut privkey.0);
+            if is_valid_privkey_scalar(&privkey.0) {
+                break;
+            }
+        }
+        KeyPair::from_privkey(privkey).expect("privkey checked above")
     }
 }
 
@@ -47,28 +191,19 @@ impl CreateKey for KeyPair {
 
     /// Create a pair from secret key
     fn from_privkey(privkey: Self::PrivKey) -> Result<Self, Self::Error> {
-        let context = &SECP256K1;
-        let s: SecretKey = SecretKey::from_slice(&privkey.0[..])?;
-        let pubkey = PublicKey::from_secret_key(context, &s);
-        let serialized = pubkey.serialize_uncompressed();
-
-        let mut pubkey = PubKey::default();
-        pubkey.0.copy_from_slice(&serialized[1..65]);
+        let pubkey = DefaultBackend::derive_pubkey(&privkey)?;
 
-        let keypair = KeyPair { privkey, pubkey };
+        let keypair = KeyPair {
+            privkey,
+            pubkey,
+            address: OnceLock::new(),
+        };
 
         Ok(keypair)
     }
 
     fn gen_keypair() -> Self {
-        let context = &SECP256K1;
-        let (s, p) = context.generate_keypair(&mut secp256k1::rand::thread_rng());
-        let serialized = p.serialize_uncompressed();
-        let mut privkey = PrivKey::default();
-        privkey.0.copy_from_slice(&s[0..32]);
-        let mut pubkey = PubKey::default();
-        pubkey.0.copy_from_slice(&serialized[1..65]);
-        KeyPair { privkey, pubkey }
+        Self::gen_keypair_with(&mut rand::thread_rng())
     }
 
     fn privkey(&self) -> &Self::PrivKey {
@@ -79,18 +214,54 @@ impl CreateKey for KeyPair {
         &self.pubkey
     }
 
+    /// The address is hashed once and cached afterwards.
     fn address(&self) -> Address {
-        pubkey_to_address(&self.pubkey)
+        *self.address.get_or_init(|| pubkey_to_address(&self.pubkey))
+    }
+}
+
+impl<'a> TryFrom<&'a PrivKey> for KeyPair {
+    type Error = Error;
+
+    /// Same as `KeyPair::from_privkey`, errors on 0 and scalars not below the order.
+    fn try_from(privkey: &'a PrivKey) -> Result<Self, Self::Error> {
+        KeyPair::from_privkey(*privkey)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{KeyPair, PrivKey};
+    use super::{
+        derive_identity, is_weak_privkey, privkey_from_be_bytes, privkey_to_be_bytes,
+        pubkey_from_str, pubkey_to_address, pubkey_to_prefixed_address, random_address,
+        random_pubkey, validate_pubkey, KeyPair, PrivKey, PubKey,
+    };
     use cita_crypto_trait::CreateKey;
     use cita_types::H256;
+    use std::convert::TryFrom;
     use std::str::FromStr;
 
+    #[test]
+    fn test_try_from_privkey() {
+        let keypair = KeyPair::gen_keypair();
+        let derived = KeyPair::try_from(keypair.privkey()).unwrap();
+        assert_eq!(derived.pubkey(), keypair.pubkey());
+        assert!(KeyPair::try_from(&PrivKey::zero()).is_err());
+        assert!(KeyPair::try_from(&PrivKey::repeat_byte(0xff)).is_err());
+    }
+
+    #[test]
+    fn test_pubkey_from_str() {
+        let pubkey = random_pubkey();
+        assert_eq!(pubkey_from_str(&format!("{:x}", pubkey)).unwrap(), pubkey);
+        assert_eq!(pubkey_from_str(&format!("{:#x}", pubkey)).unwrap(), pubkey);
+
+        let off_curve = format!("{:x}", PubKey::repeat_byte(0x01));
+        assert!(PubKey::from_str(&off_curve).is_ok());
+        assert!(pubkey_from_str(&off_curve).is_err());
+        assert!(pubkey_from_str("0x1234").is_err());
+    }
+
     #[test]
     fn from_privkey() {
         let privkey = PrivKey::from(
@@ -99,4 +270,154 @@ mod tests {
         );
         let _ = KeyPair::from_privkey(privkey).unwrap();
     }
+
+    #[cfg(feature = "passphrase")]
+    #[test]
+    fn test_privkey_from_passphrase() {
+        use super::privkey_from_passphrase;
+
+        // PBKDF2-HMAC-SHA256 at the full iteration count, from Python's hashlib
+        let expected =
+            H256::from_str("ee8349291d5a8aac1a5208b4012b808ab97d2dbc55336ba05b1abecaa5b1c500")
+                .unwrap();
+        assert_eq!(
+            privkey_from_passphrase("correct horse", b"wallet-1").unwrap(),
+            expected
+        );
+    }
+
+    #[cfg(feature = "passphrase")]
+    #[test]
+    fn test_privkey_from_passphrase_with() {
+        use super::privkey_from_passphrase_with;
+
+        let privkey = privkey_from_passphrase_with("correct horse", b"wallet-1", 1000).unwrap();
+        assert_eq!(
+            privkey_from_passphrase_with("correct horse", b"wallet-1", 1000).unwrap(),
+            privkey
+        );
+        assert_ne!(
+            privkey_from_passphrase_with("correct horse", b"wallet-2", 1000).unwrap(),
+            privkey
+        );
+        assert_ne!(
+            privkey_from_passphrase_with("correct horse", b"wallet-1", 1001).unwrap(),
+            privkey
+        );
+        assert!(KeyPair::from_privkey(privkey).is_ok());
+    }
+
+    #[test]
+    fn test_random() {
+        assert!(validate_pubkey(&random_pubkey()).is_ok());
+        assert_ne!(random_pubkey(), random_pubkey());
+        assert_ne!(random_address(), random_address());
+        assert!(validate_pubkey(&PubKey::zero()).is_err());
+    }
+
+    #[test]
+    fn test_derive_identity() {
+        let keypair = KeyPair::gen_keypair();
+        let (pubkey, address) = derive_identity(keypair.privkey()).unwrap();
+        assert_eq!(&pubkey, keypair.pubkey());
+        assert_eq!(address, pubkey_to_address(keypair.pubkey()));
+        assert!(derive_identity(&PrivKey::zero()).is_err());
+    }
+
+    #[cfg(feature = "sha3hash")]
+    #[test]
+    fn test_privkey_be_bytes() {
+        use crate::Address;
+
+        let bytes =
+            H256::from_str("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318")
+                .unwrap()
+                .0;
+        let privkey = privkey_from_be_bytes(&bytes).unwrap();
+        assert_eq!(privkey_to_be_bytes(&privkey), bytes);
+
+        let keypair = KeyPair::from_privkey(privkey).unwrap();
+        assert_eq!(
+            keypair.address(),
+            Address::from_str("2c7536e3605d9c16a7a3d7b1898e529396a65c23").unwrap()
+        );
+
+        assert!(privkey_from_be_bytes(&[0u8; 32]).is_err());
+        assert!(privkey_from_be_bytes(&[0xffu8; 32]).is_err());
+    }
+
+    /// Replays the given 32-byte blocks in order.
+    struct FixedRng(Vec<[u8; 32]>);
+
+    impl rand::RngCore for FixedRng {
+        fn next_u32(&mut self) -> u32 {
+            let mut b = [0u8; 4];
+            self.fill_bytes(&mut b);
+            u32::from_le_bytes(b)
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            let mut b = [0u8; 8];
+            self.fill_bytes(&mut b);
+            u64::from_le_bytes(b)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            let next = self.0.remove(0);
+            dest.copy_from_slice(&next[..dest.len()]);
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_gen_keypair_hardened() {
+        let strong =
+            H256::from_str("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318")
+                .unwrap();
+        let mut low_weight = [0u8; 32];
+        low_weight[31] = 0x07;
+        let mut rng = FixedRng(vec![[0x01; 32], low_weight, strong.0]);
+
+        assert!(is_weak_privkey(&PrivKey::from([0x01; 32])));
+        assert!(is_weak_privkey(&PrivKey::from(low_weight)));
+        assert!(!is_weak_privkey(&strong));
+
+        let keypair = KeyPair::gen_keypair_hardened_with(&mut rng);
+        assert_eq!(keypair.privkey(), &strong);
+        assert!(rng.0.is_empty());
+    }
+
+    #[test]
+    fn test_default_is_random() {
+        let a = KeyPair::default();
+        let b = KeyPair::default();
+        assert_ne!(a.privkey(), b.privkey());
+        assert!(validate_pubkey(a.pubkey()).is_ok());
+        assert_ne!(KeyPair::new().privkey(), &PrivKey::zero());
+    }
+
+    #[test]
+    fn test_address_cached() {
+        let keypair = KeyPair::gen_keypair();
+        assert!(keypair.address.get().is_none());
+        let address = keypair.address();
+        assert_eq!(keypair.address.get(), Some(&address));
+        assert_eq!(keypair.address(), pubkey_to_address(keypair.pubkey()));
+    }
+
+    #[test]
+    fn test_pubkey_to_prefixed_address() {
+        let pubkey = random_pubkey();
+        let core = pubkey_to_address(&pubkey);
+        let a = pubkey_to_prefixed_address(&pubkey, &[0x00]);
+        let b = pubkey_to_prefixed_address(&pubkey, &[0x1c, 0xb8]);
+        assert_ne!(a, b);
+        assert_eq!(&a[1..], core.as_bytes());
+        assert_eq!(&b[2..], core.as_bytes());
+        assert_eq!(pubkey_to_prefixed_address(&pubkey, &[]), core.as_bytes());
+    }
 }
@@ -0,0 +1,278 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ethereum V3 keystore files (Web3 Secret Storage), scrypt and AES-128-CTR only.
+
+use super::encoding::{from_hex, to_hex};
+use super::{keccak256, privkey_from_be_bytes, Error, KeyPair};
+use aes::cipher::{KeyIvInit, StreamCipher};
+use cita_crypto_trait::CreateKey;
+use rand::{thread_rng, Rng};
+use serde_json::{json, Value};
+use std::convert::TryFrom;
+use subtle::ConstantTimeEq;
+
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+
+/// scrypt parameters written by `to_keystore_json`, the ones geth uses by default.
+pub const KEYSTORE_SCRYPT_LOG_N: u8 = 18;
+pub const KEYSTORE_SCRYPT_R: u32 = 8;
+pub const KEYSTORE_SCRYPT_P: u32 = 1;
+
+/// Largest scrypt n read by `from_keystore_json`, as log2; 2^20 is geth's
+/// own upper bound.
+pub const KEYSTORE_MAX_SCRYPT_LOG_N: u8 = 20;
+/// Largest r * p read by `from_keystore_json`, geth's light setting is 48.
+pub const KEYSTORE_MAX_SCRYPT_RP: u64 = 64;
+/// Largest scrypt working memory, 128 * r * n bytes, read by `from_keystore_json`.
+pub const KEYSTORE_MAX_SCRYPT_MEMORY: u64 = 1 << 30;
+
+/// Refuse keystore scrypt parameters that would cost more than the caps above.
+///
+/// The parameters come from the file, so without a cap a hostile keystore
+/// could make the caller allocate gigabytes or spin for hours.
+fn check_scrypt_cost(log_n: u8, r: u32, p: u32) -> Result<(), Error> {
+    let (r, p) = (u64::from(r), u64::from(p));
+    if log_n > KEYSTORE_MAX_SCRYPT_LOG_N
+        || r * p > KEYSTORE_MAX_SCRYPT_RP
+        || (128 * r) << log_n > KEYSTORE_MAX_SCRYPT_MEMORY
+    {
+        return Err(Error::Unexpected(
+            "keystore: scrypt parameters above the accepted cost".into(),
+        ));
+    }
+    Ok(())
+}
+
+fn derive_key(password: &str, salt: &[u8], log_n: u8, r: u32, p: u32) -> Result<[u8; 32], Error> {
+    let params = scrypt::Params::new(log_n, r, p, 32)
+        .map_err(|_| Error::Unexpected("keystore: invalid scrypt parameters".into()))?;
+    let mut key = [0u8; 32];
+    scrypt::scrypt(password.as_bytes(), salt, &params, &mut key)
+        .map_err(|_| Error::Unexpected("keystore: invalid scrypt output length".into()))?;
+    Ok(key)
+}
+
+/// keccak256 of the second half of the derived key followed by the ciphertext.
+fn mac(key: &[u8; 32], ciphertext: &[u8]) -> [u8; 32] {
+    let mut data = key[16..32].to_vec();
+    data.extend_from_slice(ciphertext);
+    keccak256(&data).0
+}
+
+fn field<'a>(value: &'a Value, name: &str) -> Result<&'a Value, Error> {
+    value
+        .get(name)
+        .ok_or_else(|| Error::Unexpected(format!("keystore: missing {}", name)))
+}
+
+fn hex_field(value: &Value, name: &str) -> Result<Vec<u8>, Error> {
+    field(value, name)?
+        .as_str()
+        .and_then(from_hex)
+        .ok_or_else(|| Error::Unexpected(format!("keystore: {} is not hex", name)))
+}
+
+fn u32_field(value: &Value, name: &str) -> Result<u32, Error> {
+    field(value, name)?
+        .as_u64()
+        .and_then(|n| u32::try_from(n).ok())
+        .ok_or_else(|| Error::Unexpected(format!("keystore: {} is not a u32", name)))
+}
+
+fn uuid_v4() -> String {
+    let mut b: [u8; 16] = thread_rng().gen();
+    b[6] = (b[6] & 0x0f) | 0x40;
+    b[8] = (b[8] & 0x3f) | 0x80;
+    let hex = to_hex(&b);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+impl KeyPair {
+    /// Encrypt the private key into a V3 keystore JSON document.
+    ///
+    /// Key derivation with the default parameters takes about a second, by design.
+    pub fn to_keystore_json(&self, password: &str) -> Result<String, Error> {
+        self.to_keystore_json_with(password, KEYSTORE_SCRYPT_LOG_N)
+    }
+
+    fn to_keystore_json_with(&self, password: &str, log_n: u8) -> Result<String, Error> {
+        let salt: [u8; 32] = thread_rng().gen();
+        let iv: [u8; 16] = thread_rng().gen();
+        let key = derive_key(password, &salt, log_n, KEYSTORE_SCRYPT_R, KEYSTORE_SCRYPT_P)?;
+
+        let mut ciphertext = self.privkey().0;
+        Aes128Ctr::new(key[0..16].into(), &iv.into()).apply_keystream(&mut ciphertext);
+
+        let keystore = json!({
+            "address": to_hex(self.address().as_bytes()),
+            "crypto": {
+                "cipher": "aes-128-ctr",
+                "cipherparams": { "iv": to_hex(&iv) },
+                "ciphertext": to_hex(&ciphertext),
+                "kdf": "scrypt",
+                "kdfparams": {
+                    "dklen": 32,
+                    "n": 1u64 << log_n,
+                    "p": KEYSTORE_SCRYPT_P,
+                    "r": KEYSTORE_SCRYPT_R,
+                    "salt": to_hex(&salt),
+                },
+                "mac": to_hex(&mac(&key, &ciphertext)),
+            },
+            "id": uuid_v4(),
+            "version": 3,
+        });
+        Ok(keystore.to_string())
+    }
+
+    /// Decrypt a V3 keystore JSON document.
+    ///
+    /// A MAC mismatch, almost always a wrong password, gives `Error::InvalidPassword`.
+    /// scrypt parameters above `KEYSTORE_MAX_SCRYPT_LOG_N`, `KEYSTORE_MAX_SCRYPT_RP`
+    /// or `KEYSTORE_MAX_SCRYPT_MEMORY` are refused before any key derivation.
+    pub fn from_keystore_json(json: &str, password: &str) -> Result<KeyPair, Error> {
+        let keystore: Value = serde_json::from_str(json)
+            .map_err(|e| Error::Unexpected(format!("keystore: {}", e)))?;
+        if field(&keystore, "version")?.as_u64() != Some(3) {
+            return Err(Error::Unexpected(
+                "keystore: only version 3 is supported".into(),
+            ));
+        }
+        // some writers capitalize the key
+        let crypto = keystore
+            .get("crypto")
+            .or_else(|| keystore.get("Crypto"))
+            .ok_or_else(|| Error::Unexpected("keystore: missing crypto".into()))?;
+        if field(crypto, "kdf")?.as_str() != Some("scrypt")
+            || field(crypto, "cipher")?.as_str() != Some("aes-128-ctr")
+        {
+            return Err(Error::Unexpected(
+                "keystore: only scrypt with aes-128-ctr is supported".into(),
+            ));
+        }
+
+        let kdfparams = field(crypto, "kdfparams")?;
+        let n = u32_field(kdfparams, "n")?;
+        if !n.is_power_of_two() || u32_field(kdfparams, "dklen")? != 32 {
+            return Err(Error::Unexpected(
+                "keystore: invalid scrypt parameters".into(),
+            ));
+        }
+        let log_n = n.trailing_zeros() as u8;
+        let r = u32_field(kdfparams, "r")?;
+        let p = u32_field(kdfparams, "p")?;
+        check_scrypt_cost(log_n, r, p)?;
+        let key = derive_key(password, &hex_field(kdfparams, "salt")?, log_n, r, p)?;
+
+        let mut privkey = hex_field(crypto, "ciphertext")?;
+        if privkey.len() != 32 {
+            return Err(Error::InvalidPrivKey);
+        }
+        if !bool::from(mac(&key, &privkey).ct_eq(&hex_field(crypto, "mac")?[..])) {
+            return Err(Error::InvalidPassword);
+        }
+        let iv = hex_field(field(crypto, "cipherparams")?, "iv")?;
+        if iv.len() != 16 {
+            return Err(Error::Unexpected("keystore: iv is not 16 bytes".into()));
+        }
+        Aes128Ctr::new(key[0..16].into(), iv[..].into()).apply_keystream(&mut privkey);
+
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&privkey);
+        KeyPair::from_privkey(privkey_from_be_bytes(&bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::encoding::to_hex;
+    use super::super::{Error, KeyPair};
+    use super::{check_scrypt_cost, KEYSTORE_MAX_SCRYPT_LOG_N};
+    use cita_crypto_trait::CreateKey;
+
+    #[test]
+    fn test_keystore_round_trip() {
+        let keypair = KeyPair::gen_keypair();
+        let json = keypair.to_keystore_json_with("hunter2", 10).unwrap();
+        assert!(json.contains(&to_hex(keypair.address().as_bytes())));
+
+        let back = KeyPair::from_keystore_json(&json, "hunter2").unwrap();
+        assert_eq!(back.privkey(), keypair.privkey());
+        match KeyPair::from_keystore_json(&json, "hunter3") {
+            Err(Error::InvalidPassword) => {}
+            _ => panic!("wrong password accepted"),
+        }
+        assert!(KeyPair::from_keystore_json("{}", "hunter2").is_err());
+    }
+
+    #[test]
+    fn test_keystore_known_answer() {
+        // produced independently with Python's hashlib.scrypt and AES-CTR from
+        // the cryptography package, with n lowered to keep the test fast
+        let json = r#"{
+            "crypto": {
+                "cipher": "aes-128-ctr",
+                "cipherparams": { "iv": "101112131415161718191a1b1c1d1e1f" },
+                "ciphertext": "b2f0b7ccdba8068a3661fe42fe435e9d672e615aaaaad745b84a08fa1e741ee7",
+                "kdf": "scrypt",
+                "kdfparams": {
+                    "dklen": 32,
+                    "n": 1024,
+                    "p": 1,
+                    "r": 8,
+                    "salt": "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f"
+                },
+                "mac": "07d323eed6af56b50142373e46e62d92e2beaa599dec2b7461926353d05b594f"
+            },
+            "id": "3198bc9c-6672-4ab3-9995-4942343ae5b6",
+            "version": 3
+        }"#;
+        let keypair = KeyPair::from_keystore_json(json, "testpassword").unwrap();
+        assert_eq!(
+            to_hex(&keypair.privkey().0),
+            "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d"
+        );
+    }
+
+    #[test]
+    fn test_keystore_scrypt_cost_cap() {
+        let keypair = KeyPair::gen_keypair();
+        let json = keypair.to_keystore_json_with("hunter2", 10).unwrap();
+        let with_params = |n: u64, r: u64, p: u64| {
+            let mut keystore: serde_json::Value = serde_json::from_str(&json).unwrap();
+            let kdfparams = &mut keystore["crypto"]["kdfparams"];
+            kdfparams["n"] = n.into();
+            kdfparams["r"] = r.into();
+            kdfparams["p"] = p.into();
+            KeyPair::from_keystore_json(&keystore.to_string(), "hunter2")
+        };
+
+        // refused up front, none of these derive a key
+        assert!(with_params(1 << 21, 8, 1).is_err());
+        assert!(with_params(1 << 10, 8, 9).is_err());
+        assert!(with_params(1 << 20, 16, 1).is_err());
+        assert!(check_scrypt_cost(KEYSTORE_MAX_SCRYPT_LOG_N, 8, 1).is_ok());
+        assert!(check_scrypt_cost(12, 8, 6).is_ok());
+
+        assert!(with_params(1 << 10, 8, 1).is_ok());
+    }
+}
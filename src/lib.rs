@@ -12,11 +12,14 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+#[cfg(not(any(feature = "secp256k1", feature = "libsecp256k1-backend")))]
+compile_error!("enable the `secp256k1` feature or the `libsecp256k1-backend` feature");
+
+#[cfg(feature = "secp256k1")]
 use lazy_static::lazy_static;
 
 pub type PrivKey = H256;
 pub type PubKey = H512;
-pub type Message = H256;
 
 pub const ADDR_BYTES_LEN: usize = 20;
 pub const PUBKEY_BYTES_LEN: usize = 64;
@@ -24,18 +27,133 @@ pub const PRIVKEY_BYTES_LEN: usize = 32;
 pub const SIGNATURE_BYTES_LEN: usize = 65;
 pub const HASH_BYTES_LEN: usize = 32;
 
+mod address;
+mod backend;
+#[cfg(feature = "secp256k1")]
+mod ecdh;
+mod encoding;
 mod error;
+mod ethereum;
+mod extended_signature;
+#[cfg(feature = "arbitrary")]
+mod fuzz;
 mod keypair;
+#[cfg(feature = "keystore")]
+mod keystore;
+mod message;
+mod nonce_reuse;
+mod pending_verification;
+mod prepared_message;
+mod seen_signatures;
 mod signature;
+mod signature_pool;
+mod signed_message;
 mod signer;
+mod signer_set;
+#[cfg(feature = "proptest")]
+mod strategy;
+mod verifier;
+#[cfg(test)]
+mod wycheproof;
 
+pub use self::address::*;
+pub use self::backend::*;
+#[cfg(feature = "secp256k1")]
+pub use self::ecdh::*;
 pub use self::error::*;
+pub use self::ethereum::*;
+pub use self::extended_signature::*;
+#[cfg(feature = "arbitrary")]
+pub use self::fuzz::*;
 pub use self::keypair::*;
+#[cfg(feature = "keystore")]
+pub use self::keystore::*;
+pub use self::message::*;
+pub use self::nonce_reuse::*;
+pub use self::pending_verification::*;
+pub use self::prepared_message::*;
+pub use self::seen_signatures::*;
 pub use self::signature::*;
+pub use self::signature_pool::*;
+pub use self::signed_message::*;
 pub use self::signer::Signer;
-use cita_types::{Address, H256, H512};
+pub use self::signer_set::*;
+#[cfg(feature = "proptest")]
+pub use self::strategy::*;
+pub use self::verifier::Verifier;
+use cita_types::{H256, H512};
+#[cfg(feature = "secp256k1")]
 use secp256k1::All;
 
+#[cfg(feature = "secp256k1")]
 lazy_static! {
+    /// Context shared by every sign, verify and recover call.
+    ///
+    /// It is a full `Secp256k1::new()` context, capable of both signing and
+    /// verification; building one is far more expensive than a signature, so
+    /// it is created once on first use and never per call.
     pub static ref SECP256K1: secp256k1::Secp256k1<All> = secp256k1::Secp256k1::new();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{verify_public, Address, KeyPair, Message, PubKey, Signature};
+    use cita_crypto_trait::{CreateKey, Sign};
+    use static_assertions::assert_impl_all;
+    use std::sync::Arc;
+    use std::thread;
+
+    assert_impl_all!(Signature: Send, Sync);
+    assert_impl_all!(PubKey: Send, Sync);
+    assert_impl_all!(Address: Send, Sync);
+    assert_impl_all!(KeyPair: Send, Sync);
+    #[cfg(feature = "secp256k1")]
+    assert_impl_all!(secp256k1::Secp256k1<secp256k1::All>: Send, Sync);
+
+    #[test]
+    fn test_pubkey_and_address_as_map_keys() {
+        use std::collections::HashMap;
+
+        let keypairs: Vec<_> = (0..3).map(|_| KeyPair::gen_keypair()).collect();
+        let by_pubkey: HashMap<PubKey, usize> =
+            keypairs.iter().map(|k| *k.pubkey()).zip(0..).collect();
+        let by_address: HashMap<Address, usize> =
+            keypairs.iter().map(|k| k.address()).zip(0..).collect();
+        for (i, keypair) in keypairs.iter().enumerate() {
+            assert_eq!(by_pubkey[keypair.pubkey()], i);
+            assert_eq!(by_address[&Address::from(keypair.pubkey())], i);
+        }
+        assert!(!by_address.contains_key(&Address::zero()));
+    }
+
+    #[test]
+    fn test_concurrent_verify() {
+        let keypair = KeyPair::gen_keypair();
+        let pairs: Arc<Vec<_>> = Arc::new(
+            (0..64)
+                .map(|i: u32| {
+                    let message = Message::hash(i.to_string());
+                    (
+                        Signature::sign(keypair.privkey(), &message).unwrap(),
+                        message,
+                    )
+                })
+                .collect(),
+        );
+        let pubkey = *keypair.pubkey();
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let pairs = Arc::clone(&pairs);
+                thread::spawn(move || {
+                    pairs
+                        .iter()
+                        .all(|(sig, message)| verify_public(&pubkey, sig, message).unwrap())
+                })
+            })
+            .collect();
+        for handle in handles {
+            assert!(handle.join().unwrap());
+        }
+    }
+}
@@ -0,0 +1,69 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use cita_types::H256;
+use hashable::Hashable;
+
+/// Digest that gets signed.
+///
+/// A distinct type rather than an alias of `H256`, so an arbitrary 32-byte
+/// value can not be signed by accident; a message comes from hashing data
+/// with `hash`, or from a digest computed elsewhere with `from_digest`:
+///
+/// ```compile_fail
+/// use cita_secp256k1::{sign, PrivKey};
+/// use cita_types::H256;
+///
+/// let not_a_digest = H256::from_low_u64_be(1);
+/// sign(&PrivKey::from_low_u64_be(1), &not_a_digest);
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Message(H256);
+
+impl Message {
+    /// Hash `data` with the hash function the crate is built with.
+    pub fn hash<T: AsRef<[u8]>>(data: T) -> Message {
+        Message(data.as_ref().crypt_hash())
+    }
+
+    /// Wrap a digest computed elsewhere, e.g. `keccak256` for Ethereum.
+    pub fn from_digest(digest: H256) -> Message {
+        Message(digest)
+    }
+
+    /// All-zero digest, almost always an unhashed default rather than a hash.
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+
+    pub fn as_fixed_bytes(&self) -> &[u8; 32] {
+        self.0.as_fixed_bytes()
+    }
+}
+
+impl From<Message> for H256 {
+    fn from(message: Message) -> Self {
+        message.0
+    }
+}
+
+impl AsRef<[u8]> for Message {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
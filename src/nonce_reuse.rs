@@ -0,0 +1,152 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#[cfg(feature = "secp256k1")]
+use super::signature::{from_secp_pubkey, invert_scalar, reduce_scalar};
+#[cfg(feature = "secp256k1")]
+use super::{recover, Error, PrivKey, SECP256K1};
+use super::{Message, Signature};
+use cita_types::{H256FastMap, H256};
+#[cfg(feature = "secp256k1")]
+use secp256k1::{PublicKey, Scalar, SecretKey};
+
+/// Find two signatures sharing `r` over different messages.
+///
+/// `r` only depends on the nonce, so this means a nonce was reused and the
+/// private key behind both signatures is leaked. Returns the indices of the
+/// first such pair; the same signature repeated over the same message is
+/// not reported.
+pub fn detect_nonce_reuse(sigs: &[(Signature, Message)]) -> Option<(usize, usize)> {
+    let mut first_by_r: H256FastMap<usize> = H256FastMap::default();
+    for (i, (sig, message)) in sigs.iter().enumerate() {
+        let first = *first_by_r.entry(H256::from_slice(sig.r())).or_insert(i);
+        if sigs[first].1 != *message {
+            return Some((first, i));
+        }
+    }
+    None
+}
+
+/// Reduce a message digest to a non-zero scalar.
+#[cfg(feature = "secp256k1")]
+fn message_scalar(message: &Message) -> Result<SecretKey, Error> {
+    Ok(SecretKey::from_slice(
+        &reduce_scalar(message.as_fixed_bytes()).0,
+    )?)
+}
+
+/// Recover the private key behind two signatures that reused a nonce.
+///
+/// With `s_i = k^-1 (z_i + r d)`, the nonce is `k = (z1 - z2) / (s1 - s2)`
+/// and the key `d = (s1 k - z1) / r`. Either `s` may have been negated by
+/// low-s normalization, so both signs of `s2` are tried and the candidate is
+/// checked against the public key recovered from `sig1`.
+///
+/// For incident response only: confirming a key is compromised so it can be
+/// rotated. The arithmetic is not constant time, and done with the scalar
+/// operations of the `secp256k1` crate, so it needs that feature.
+#[cfg(feature = "secp256k1")]
+pub fn recover_privkey_from_reuse(
+    sig1: &Signature,
+    msg1: &Message,
+    sig2: &Signature,
+    msg2: &Message,
+) -> Result<PrivKey, Error> {
+    sig1.validate()?;
+    sig2.validate()?;
+    if sig1.r() != sig2.r() {
+        return Err(Error::InvalidSignature);
+    }
+    if msg1 == msg2 {
+        return Err(Error::InvalidMessage);
+    }
+    let pubkey = recover(sig1, msg1)?;
+
+    let r = SecretKey::from_slice(sig1.r())?;
+    let s1 = SecretKey::from_slice(sig1.s())?;
+    let s2 = SecretKey::from_slice(sig2.s())?;
+    let z1 = message_scalar(msg1)?;
+    let z2 = message_scalar(msg2)?;
+    let dz = z1.add_tweak(&Scalar::from(z2.negate()))?;
+
+    for s2 in [s2, s2.negate()].iter() {
+        let ds = match s1.add_tweak(&Scalar::from(s2.negate())) {
+            Ok(ds) => ds,
+            Err(_) => continue,
+        };
+        let k = dz.mul_tweak(&Scalar::from(invert_scalar(&ds)))?;
+        let d = match s1
+            .mul_tweak(&Scalar::from(k))?
+            .add_tweak(&Scalar::from(z1.negate()))
+        {
+            Ok(rd) => rd.mul_tweak(&Scalar::from(invert_scalar(&r)))?,
+            Err(_) => continue,
+        };
+        if from_secp_pubkey(&PublicKey::from_secret_key(&SECP256K1, &d)) == pubkey {
+            return Ok(PrivKey::from(d.secret_bytes()));
+        }
+    }
+    Err(Error::InvalidSignature)
+}
+
+#[cfg(all(test, feature = "secp256k1"))]
+mod tests {
+    use super::super::{sign_with_nonce, KeyPair, Message, Signature};
+    use super::{detect_nonce_reuse, recover_privkey_from_reuse};
+    use cita_crypto_trait::{CreateKey, Sign};
+
+    #[test]
+    fn test_detect_nonce_reuse() {
+        let keypair = KeyPair::gen_keypair();
+        let messages: Vec<_> = ["a", "b", "c"].iter().map(Message::hash).collect();
+        let mut sigs: Vec<_> = messages
+            .iter()
+            .map(|m| (Signature::sign(keypair.privkey(), m).unwrap(), *m))
+            .collect();
+        sigs.push(sigs[0].clone());
+        assert_eq!(detect_nonce_reuse(&sigs), None);
+
+        let k = [0x42u8; 32];
+        sigs.push((
+            sign_with_nonce(keypair.privkey(), &messages[1], &k).unwrap(),
+            messages[1],
+        ));
+        sigs.push((
+            sign_with_nonce(keypair.privkey(), &messages[2], &k).unwrap(),
+            messages[2],
+        ));
+        assert_eq!(detect_nonce_reuse(&sigs), Some((4, 5)));
+    }
+
+    #[test]
+    fn test_recover_privkey_from_reuse() {
+        let keypair = KeyPair::gen_keypair();
+        let m1 = Message::hash("a");
+        let m2 = Message::hash("b");
+        // different nonces cover both the same and opposite low-s negation
+        for k in [[0x42u8; 32], [0x17u8; 32], [0x99u8; 32], [0x05u8; 32]].iter() {
+            let sig1 = sign_with_nonce(keypair.privkey(), &m1, k).unwrap();
+            let sig2 = sign_with_nonce(keypair.privkey(), &m2, k).unwrap();
+            assert_eq!(
+                &recover_privkey_from_reuse(&sig1, &m1, &sig2, &m2).unwrap(),
+                keypair.privkey()
+            );
+        }
+
+        let sig1 = Signature::sign(keypair.privkey(), &m1).unwrap();
+        let sig2 = Signature::sign(keypair.privkey(), &m2).unwrap();
+        assert!(recover_privkey_from_reuse(&sig1, &m1, &sig2, &m2).is_err());
+        assert!(recover_privkey_from_reuse(&sig1, &m1, &sig1, &m1).is_err());
+    }
+}
@@ -0,0 +1,79 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{validate_pubkey, verify_public, Error, Message, PubKey, Signature};
+
+/// Signature waiting for its message to be assembled.
+///
+/// The signature ranges and the public key are checked at construction, so
+/// malformed input is rejected right away and only the verification itself
+/// is deferred.
+#[derive(Debug, Clone)]
+pub struct PendingVerification {
+    pubkey: PubKey,
+    signature: Signature,
+}
+
+impl PendingVerification {
+    pub fn new(pubkey: PubKey, signature: Signature) -> Result<PendingVerification, Error> {
+        signature.validate()?;
+        validate_pubkey(&pubkey)?;
+        Ok(PendingVerification { pubkey, signature })
+    }
+
+    pub fn pubkey(&self) -> &PubKey {
+        &self.pubkey
+    }
+
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    /// Verify the signature once the message is ready.
+    pub fn finish(self, message: &Message) -> Result<bool, Error> {
+        verify_public(&self.pubkey, &self.signature, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{Error, KeyPair, Message, Signature};
+    use super::PendingVerification;
+    use cita_crypto_trait::{CreateKey, Sign};
+
+    #[test]
+    fn test_finish() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+
+        let pending = PendingVerification::new(*keypair.pubkey(), sig.clone()).unwrap();
+        assert!(pending.finish(&message).unwrap());
+
+        let pending = PendingVerification::new(*keypair.pubkey(), sig).unwrap();
+        assert!(!pending.finish(&Message::hash("other")).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_at_construction() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let mut sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        sig.0[64] = 4;
+        assert!(matches!(
+            PendingVerification::new(*keypair.pubkey(), sig),
+            Err(Error::VOutOfRange)
+        ));
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{Backend, DefaultBackend, Error, Message, PubKey, Signature};
+
+/// Message parsed once by the default backend, for verifying many signatures
+/// over it, e.g. votes on the same block.
+///
+/// With the `libsecp256k1` backend parsing reduces the digest to a scalar,
+/// with the C backend it is only a copy. Pair it with a `Verifier`, which
+/// keeps the public key parsed too, `verify_public_prepared` still parses the
+/// key on every call. Both are cheap next to the verification itself, so the
+/// saving is a few percent at most.
+#[derive(Debug, Clone, Copy)]
+pub struct PreparedMessage {
+    message: Message,
+    parsed: <DefaultBackend as Backend>::ParsedMessage,
+}
+
+impl PreparedMessage {
+    pub fn new(message: &Message) -> Result<PreparedMessage, Error> {
+        Ok(PreparedMessage {
+            message: *message,
+            parsed: DefaultBackend::parse_message(message)?,
+        })
+    }
+
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    pub(crate) fn parsed(&self) -> &<DefaultBackend as Backend>::ParsedMessage {
+        &self.parsed
+    }
+}
+
+pub fn verify_public_prepared(
+    pubkey: &PubKey,
+    signature: &Signature,
+    prepared: &PreparedMessage,
+) -> Result<bool, Error> {
+    DefaultBackend::verify_prepared(
+        &DefaultBackend::parse_pubkey(pubkey)?,
+        signature,
+        &prepared.parsed,
+    )
+}
+
+pub fn recover_prepared(
+    signature: &Signature,
+    prepared: &PreparedMessage,
+) -> Result<PubKey, Error> {
+    DefaultBackend::recover_prepared(signature, &prepared.parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{recover, verify_public, KeyPair, Message, Signature, Verifier};
+    use super::{recover_prepared, verify_public_prepared, PreparedMessage};
+    use cita_crypto_trait::{CreateKey, Sign};
+
+    #[test]
+    fn test_prepared_matches_unprepared() {
+        let message = Message::hash("block");
+        let prepared = PreparedMessage::new(&message).unwrap();
+        assert_eq!(prepared.message(), &message);
+
+        let other = KeyPair::gen_keypair();
+        for _ in 0..4 {
+            let keypair = KeyPair::gen_keypair();
+            let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+            assert!(verify_public_prepared(keypair.pubkey(), &sig, &prepared).unwrap());
+            let verifier = Verifier::new(*keypair.pubkey()).unwrap();
+            assert!(verifier.verify_prepared(&sig, &prepared).unwrap());
+            assert!(!Verifier::new(*other.pubkey())
+                .unwrap()
+                .verify_prepared(&sig, &prepared)
+                .unwrap());
+            assert_eq!(
+                verify_public_prepared(other.pubkey(), &sig, &prepared).unwrap(),
+                verify_public(other.pubkey(), &sig, &message).unwrap()
+            );
+            assert_eq!(
+                recover_prepared(&sig, &prepared).unwrap(),
+                recover(&sig, &message).unwrap()
+            );
+        }
+    }
+}
@@ -0,0 +1,79 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::Signature;
+use cita_types::{H256FastSet, H256};
+use hashable::Hashable;
+
+/// Set of already seen signatures, for replay detection.
+///
+/// Entries are keyed by the hash of r || s only: signing is deterministic
+/// (RFC 6979), so a replay has the same r and s, whatever its `v` byte says.
+#[derive(Debug, Default, Clone)]
+pub struct SeenSignatures {
+    seen: H256FastSet,
+}
+
+impl SeenSignatures {
+    pub fn new() -> SeenSignatures {
+        SeenSignatures::default()
+    }
+
+    /// Record the signature, returning `false` if it was seen before.
+    pub fn insert_if_new(&mut self, sig: &Signature) -> bool {
+        self.seen.insert(Self::key(sig))
+    }
+
+    pub fn contains(&self, sig: &Signature) -> bool {
+        self.seen.contains(&Self::key(sig))
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    fn key(sig: &Signature) -> H256 {
+        (&sig.0[0..64]).crypt_hash()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{KeyPair, Message};
+    use super::{SeenSignatures, Signature};
+    use cita_crypto_trait::{CreateKey, Sign};
+
+    #[test]
+    fn test_insert_if_new() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        let mut seen = SeenSignatures::new();
+
+        assert!(seen.insert_if_new(&sig));
+        assert!(!seen.insert_if_new(&sig));
+
+        let mut other_v = sig.clone();
+        other_v.0[64] ^= 1;
+        assert!(!seen.insert_if_new(&other_v));
+
+        let other = Signature::sign(keypair.privkey(), &Message::hash("other")).unwrap();
+        assert!(seen.insert_if_new(&other));
+        assert_eq!(seen.len(), 2);
+    }
+}
@@ -12,30 +12,79 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use super::encoding::{from_base64, from_hex, to_hex};
+#[cfg(all(test, feature = "secp256k1"))]
+use super::SECP256K1;
 use super::{
-    pubkey_to_address, Address, Error, Message, PrivKey, PubKey, SECP256K1, SIGNATURE_BYTES_LEN,
+    addresses_ct_eq, derive_identity, pubkey_to_address, recover_with_convention, Address, Backend,
+    DefaultBackend, Error, ExtendedSignature, Message, PrivKey, PubKey, RecoveryConvention,
+    SIGNATURE_BYTES_LEN,
 };
+#[cfg(feature = "secp256k1")]
+use super::{Secp256k1Backend, PUBKEY_BYTES_LEN};
 use cita_crypto_trait::Sign;
 use cita_types::H256;
-use rlp::*;
-use rustc_serialize::hex::ToHex;
-use secp256k1::{PublicKey, SecretKey};
-use secp256k1::{
-    ecdsa::RecoverableSignature, ecdsa::RecoveryId, Error as SecpError,
-    Message as SecpMessage,
-};
+#[cfg(feature = "rlp")]
+use rlp::{Decodable, DecoderError, Encodable, Rlp, RlpStream};
+#[cfg(feature = "secp256k1")]
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+#[cfg(feature = "secp256k1")]
+use secp256k1::{PublicKey, Scalar, SecretKey};
+#[cfg(feature = "serde")]
 use serde::de::{Error as SerdeError, SeqAccess, Visitor};
+#[cfg(feature = "serde")]
 use serde::ser::SerializeSeq;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::cmp::PartialEq;
-use std::convert::From;
+use std::collections::{HashMap, HashSet};
+use std::convert::{From, TryFrom, TryInto};
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::ops::{Deref, DerefMut};
 use std::str::FromStr;
+use std::time::{Duration, Instant};
+use subtle::{Choice, ConditionallySelectable};
+
+/// Order of the secp256k1 group, big-endian.
+const CURVE_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xfe,
+    0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36, 0x41, 0x41,
+];
+
+/// Half the group order, the largest "low" `s`, big-endian.
+const HALF_CURVE_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// Big-endian `a - b` without branches, returning the difference and the final borrow.
+fn sub_be(a: &[u8; 32], b: &[u8; 32]) -> ([u8; 32], u8) {
+    let mut out = [0u8; 32];
+    let mut borrow = 0u16;
+    for i in (0..32).rev() {
+        let d = 0x100 + u16::from(a[i]) - u16::from(b[i]) - borrow;
+        out[i] = d as u8;
+        borrow = 1 - (d >> 8);
+    }
+    (out, borrow as u8)
+}
+
+/// Version byte of the format written by `Signature::to_versioned_bytes`.
+pub const SIGNATURE_FORMAT_VERSION: u8 = 1;
 
 pub struct Signature(pub [u8; 65]);
 
+/// Copy a 65-byte signature out of a slice, checking its length.
+pub(crate) fn bytes_to_sig(bytes: &[u8]) -> Result<[u8; 65], Error> {
+    if bytes.len() != SIGNATURE_BYTES_LEN {
+        return Err(Error::InvalidSignature);
+    }
+    let mut sig = [0u8; 65];
+    sig.copy_from_slice(bytes);
+    Ok(sig)
+}
+
 impl Signature {
     /// Get a slice into the 'r' portion of the data.
     pub fn r(&self) -> &[u8] {
@@ -47,11 +96,47 @@ impl Signature {
         &self.0[32..64]
     }
 
+    /// Get the 'r' portion of the data with its length in the type.
+    pub fn r_array(&self) -> &[u8; 32] {
+        self.0[0..32].try_into().expect("r is 32 bytes")
+    }
+
+    /// Get the 's' portion of the data with its length in the type.
+    pub fn s_array(&self) -> &[u8; 32] {
+        self.0[32..64].try_into().expect("s is 32 bytes")
+    }
+
     /// Get the recovery byte.
     pub fn v(&self) -> u8 {
         self.0[64]
     }
 
+    /// Get r || s without the recovery byte, e.g. for a `v` independent id.
+    pub fn rs_bytes(&self) -> [u8; 64] {
+        let mut rs = [0u8; 64];
+        rs.copy_from_slice(&self.0[0..64]);
+        rs
+    }
+
+    /// Get the 65 bytes r || s || v, same as `Into<[u8; 65]>`.
+    pub fn to_bytes(&self) -> [u8; 65] {
+        self.0
+    }
+
+    /// Bytes for the `signature` field of a CITA `UnverifiedTransaction`.
+    ///
+    /// CITA stores the 65 bytes r || s || v unchanged, `v` being the raw
+    /// recovery id 0 or 1; the field is protobuf `bytes`, so no length prefix
+    /// or RLP list is added here.
+    pub fn to_cita_tx_signature(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+
+    /// Create a signature object from r || s || v, same as `From<[u8; 65]>`.
+    pub fn from_bytes(bytes: [u8; 65]) -> Signature {
+        Signature(bytes)
+    }
+
     /// Create a signature object from the sig.
     pub fn from_rsv(r: &H256, s: &H256, v: u8) -> Signature {
         let mut sig = [0u8; 65];
@@ -61,24 +146,259 @@ impl Signature {
         Signature(sig)
     }
 
+    /// Create a signature object from a 64-byte r || s and a separate recovery id.
+    pub fn from_compact(rs: &[u8; 64], recovery_id: u8) -> Result<Signature, Error> {
+        if recovery_id > 3 {
+            return Err(Error::VOutOfRange);
+        }
+        let mut sig = [0u8; 65];
+        sig[0..64].copy_from_slice(rs);
+        sig[64] = recovery_id;
+        Ok(Signature(sig))
+    }
+
+    /// EIP-2098 compact form, r || s with the y parity stored in the top bit of s.
+    ///
+    /// Only defined for low `s` and a recovery id of 0 or 1, which is what
+    /// `sign` produces; anything else does not survive `from_eip2098`.
+    pub fn to_eip2098(&self) -> [u8; 64] {
+        let mut compact = self.rs_bytes();
+        compact[32] |= (self.v() & 1) << 7;
+        compact
+    }
+
+    /// Create a signature object from the EIP-2098 compact form.
+    pub fn from_eip2098(bytes: &[u8; 64]) -> Result<Signature, Error> {
+        let mut rs = *bytes;
+        let y_parity = rs[32] >> 7;
+        rs[32] &= 0x7f;
+        let sig = Signature::from_compact(&rs, y_parity)?;
+        if !sig.is_low_s() {
+            return Err(Error::SOutOfRange);
+        }
+        Ok(sig)
+    }
+
+    /// Import a Bitcoin `signmessage` signature, header || r || s.
+    ///
+    /// The header is 27 + recovery id, plus 4 when the signer's address uses
+    /// the compressed public key; that flag is returned next to the signature.
+    /// The signed digest is Bitcoin's double SHA-256 of the prefixed message,
+    /// not this crate's `Message::hash`.
+    pub fn from_bitcoin_message_sig(bytes: &[u8; 65]) -> Result<(Signature, bool), Error> {
+        if !(27..=34).contains(&bytes[0]) {
+            return Err(Error::VOutOfRange);
+        }
+        let flags = bytes[0] - 27;
+        let rs: &[u8; 64] = bytes[1..65].try_into().expect("r || s is 64 bytes");
+        Ok((Signature::from_compact(rs, flags & 3)?, flags & 4 != 0))
+    }
+
+    /// Abbreviated hex for logs, `0x` + first and last 6 digits of r || s, then `v`.
+    pub fn to_short_string(&self) -> String {
+        let rs = to_hex(&self.0[0..64]);
+        format!("0x{}…{} (v={})", &rs[..6], &rs[122..], self.v())
+    }
+
     /// Check if this is a "low" signature.
+    ///
+    /// Low means `s <= (n - 1) / 2`, so the boundary value itself is low; this
+    /// is the BIP-62 rule, and the one libsecp256k1 and go-ethereum apply.
     pub fn is_low_s(&self) -> bool {
         H256::from_slice(self.s())
             <= H256::from_str("7FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF5D576E7357A4501DDFE92F46681B20A0")
                 .unwrap()
     }
 
+    /// Check if `s` is exactly `(n - 1) / 2`, the largest low value.
+    pub fn is_boundary_s(&self) -> bool {
+        self.s() == HALF_CURVE_ORDER
+    }
+
     /// Check if each component of the signature is in range.
     pub fn is_valid(&self) -> bool {
-        self.v() <= 1
-            && H256::from_slice(self.r())
-                < H256::from_str("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141")
-                    .unwrap()
-            && H256::from_slice(self.r()) >= H256::from_str("1").unwrap()
-            && H256::from_slice(self.s())
-                < H256::from_str("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141")
-                    .unwrap()
-            && H256::from_slice(self.s()) >= H256::from_str("1").unwrap()
+        self.validate().is_ok()
+    }
+
+    /// Check each component of the signature, reporting the first one out of range.
+    pub fn validate(&self) -> Result<(), Error> {
+        let order =
+            H256::from_str("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141")
+                .unwrap();
+        let zero = H256::zero();
+        let r = H256::from_slice(self.r());
+        if r >= order || r == zero {
+            return Err(Error::ROutOfRange);
+        }
+        let s = H256::from_slice(self.s());
+        if s >= order || s == zero {
+            return Err(Error::SOutOfRange);
+        }
+        if self.v() > 1 {
+            return Err(Error::VOutOfRange);
+        }
+        Ok(())
+    }
+
+    /// Replace a high `s` by `n - s`, flipping the recovery parity to match.
+    ///
+    /// Runs in constant time, `s` is never branched on.
+    pub fn normalize_s(&mut self) {
+        let mut s = [0u8; 32];
+        s.copy_from_slice(self.s());
+        // half_n - s borrows exactly when s is high
+        let (_, is_high) = sub_be(&HALF_CURVE_ORDER, &s);
+        let is_high = Choice::from(is_high);
+        let (negated, _) = sub_be(&CURVE_ORDER, &s);
+        for i in 0..32 {
+            self.0[32 + i] = u8::conditional_select(&s[i], &negated[i], is_high);
+        }
+        self.0[64] ^= is_high.unwrap_u8();
+    }
+
+    /// Recover the public key, falling back to `default` on any error.
+    ///
+    /// For best-effort display only, never use it where security matters:
+    /// the failure is silently swallowed.
+    pub fn recover_or(&self, message: &Message, default: PubKey) -> PubKey {
+        recover(self, message).unwrap_or(default)
+    }
+
+    /// Check that no smaller recovery id recovers the same public key.
+    ///
+    /// The ids 0 to 3 always recover distinct keys, so what this rejects is an
+    /// inflated `v` such as the legacy 27/28 encoding, which `recover_lenient`
+    /// resolves to the same key as 0/1.
+    pub fn has_canonical_recovery(&self, message: &Message) -> Result<bool, Error> {
+        let pubkey = recover_lenient(self, message)?;
+        for id in 0..self.v().min(4) {
+            let mut candidate = self.clone();
+            candidate.0[64] = id;
+            if recover(&candidate, message).ok() == Some(pubkey) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Borrow r, s and v out of a 65-byte buffer without copying.
+    pub fn view(bytes: &[u8]) -> Result<(&[u8; 32], &[u8; 32], u8), Error> {
+        if bytes.len() != SIGNATURE_BYTES_LEN {
+            return Err(Error::InvalidSignature);
+        }
+        let r = bytes[0..32].try_into().expect("length checked above");
+        let s = bytes[32..64].try_into().expect("length checked above");
+        Ok((r, s, bytes[64]))
+    }
+
+    /// Serialize with a leading format version byte, for long-term storage.
+    pub fn to_versioned_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(SIGNATURE_BYTES_LEN + 1);
+        bytes.push(SIGNATURE_FORMAT_VERSION);
+        bytes.extend_from_slice(&self.0);
+        bytes
+    }
+
+    /// Deserialize bytes produced by `to_versioned_bytes`.
+    pub fn from_versioned_bytes(bytes: &[u8]) -> Result<Signature, Error> {
+        match bytes.split_first() {
+            Some((&SIGNATURE_FORMAT_VERSION, rest)) => Ok(Signature(bytes_to_sig(rest)?)),
+            Some((&version, _)) => Err(Error::UnsupportedVersion(version)),
+            None => Err(Error::InvalidSignature),
+        }
+    }
+
+    /// Create a signature object from a DER encoded signature.
+    ///
+    /// DER carries no recovery id, so `v` is set to 0.
+    pub fn from_der(bytes: &[u8]) -> Result<Signature, Error> {
+        #[cfg(feature = "secp256k1")]
+        let rs = secp256k1::ecdsa::Signature::from_der(bytes)?.serialize_compact();
+        #[cfg(not(feature = "secp256k1"))]
+        let rs = libsecp256k1::Signature::parse_der(bytes)?.serialize();
+        let mut data_arr = [0u8; 65];
+        data_arr[0..64].copy_from_slice(&rs);
+        Ok(Signature(data_arr))
+    }
+
+    /// Create a signature object from 65-byte compact, 64-byte compact or DER input.
+    ///
+    /// Inputs without a recovery id get `v` set to 0.
+    pub fn from_any(bytes: &[u8]) -> Result<Signature, Error> {
+        match bytes.len() {
+            SIGNATURE_BYTES_LEN => Ok(Signature(bytes_to_sig(bytes)?)),
+            64 => {
+                let mut data_arr = [0u8; 65];
+                data_arr[0..64].copy_from_slice(bytes);
+                Ok(Signature(data_arr))
+            }
+            _ => Signature::from_der(bytes),
+        }
+    }
+
+    /// Serialize in libsecp256k1's internal `secp256k1_ecdsa_recoverable_signature` layout.
+    ///
+    /// That struct is opaque: r and s are stored as native scalars, which on
+    /// 64-bit little-endian targets means reversed byte order, followed by the
+    /// recovery id. Only meant for handing the struct to C code as is, so it is
+    /// only offered on targets where that layout is known, and with the
+    /// `secp256k1` feature.
+    #[cfg(all(
+        feature = "secp256k1",
+        target_pointer_width = "64",
+        target_endian = "little"
+    ))]
+    pub fn to_libsecp_recoverable(&self) -> Result<[u8; 65], Error> {
+        let rsig = to_recoverable(self)?;
+        // SAFETY: `ffi::RecoverableSignature` is a `repr(C)` wrapper around
+        // `[c_uchar; 65]`, so the pointer is valid for a 65 byte read and
+        // `[u8; 65]` has no alignment requirement beyond 1.
+        Ok(unsafe { *(rsig.as_ptr() as *const [u8; 65]) })
+    }
+
+    /// Create a signature object from libsecp256k1's internal recoverable layout.
+    ///
+    /// r and s must be reduced scalars, anything else is rejected before it
+    /// reaches the C library.
+    #[cfg(all(
+        feature = "secp256k1",
+        target_pointer_width = "64",
+        target_endian = "little"
+    ))]
+    pub fn from_libsecp_recoverable(bytes: &[u8; 65]) -> Result<Signature, Error> {
+        if bytes[64] > 3 {
+            return Err(Error::VOutOfRange);
+        }
+        let mut data = [0u8; 65];
+        data[0..32].copy_from_slice(&bytes[0..32]);
+        data[0..32].reverse();
+        data[32..64].copy_from_slice(&bytes[32..64]);
+        data[32..64].reverse();
+        data[64] = bytes[64];
+        let sig = Signature(data);
+        // zero or unreduced r and s never get written into the struct
+        sig.validate()?;
+        if sig.to_libsecp_recoverable()? != *bytes {
+            return Err(Error::InvalidSignature);
+        }
+        Ok(sig)
+    }
+}
+
+#[cfg(feature = "k256-interop")]
+impl Signature {
+    /// Convert into a `k256` recoverable signature.
+    ///
+    /// Both layouts are r || s || v with `v` being the raw recovery id,
+    /// but `k256` only accepts the ids 0 and 1.
+    pub fn to_k256(&self) -> Result<k256::ecdsa::recoverable::Signature, Error> {
+        k256::ecdsa::recoverable::Signature::try_from(&self.0[..])
+            .map_err(|_| Error::InvalidSignature)
+    }
+
+    /// Create a signature object from a `k256` recoverable signature.
+    pub fn from_k256(sig: &k256::ecdsa::recoverable::Signature) -> Signature {
+        Signature::from(sig.as_ref())
     }
 }
 
@@ -90,16 +410,31 @@ impl PartialEq for Signature {
     }
 }
 
+/// Compares all 65 bytes, a slice of any other length is never equal.
+impl PartialEq<[u8]> for Signature {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.0[..] == *other
+    }
+}
+
+impl<'a> PartialEq<&'a [u8]> for Signature {
+    fn eq(&self, other: &&'a [u8]) -> bool {
+        self.0[..] == **other
+    }
+}
+
+#[cfg(feature = "rlp")]
 impl Decodable for Signature {
     fn decode(rlp: &Rlp) -> Result<Self, DecoderError> {
-        rlp.decoder().decode_value(|bytes| {
-            let mut sig = [0u8; 65];
-            sig[0..65].copy_from_slice(bytes);
-            Ok(Signature(sig))
-        })
+        rlp.decoder()
+            .decode_value(|bytes| match bytes_to_sig(bytes) {
+                Ok(sig) => Ok(Signature(sig)),
+                Err(_) => Err(DecoderError::RlpInvalidLength),
+            })
     }
 }
 
+#[cfg(feature = "rlp")]
 impl Encodable for Signature {
     fn rlp_append(&self, s: &mut RlpStream) {
         s.encoder().encode_value(&self.0[0..65]);
@@ -107,6 +442,7 @@ impl Encodable for Signature {
 }
 
 // TODO: Maybe it should be implemented with rust macro(https://github.com/rust-lang/rfcs/issues/1038)
+#[cfg(feature = "serde")]
 impl<'de> Deserialize<'de> for Signature {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -118,7 +454,11 @@ impl<'de> Deserialize<'de> for Signature {
             type Value = Signature;
 
             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("secp256k1 signature")
+                write!(
+                    formatter,
+                    "secp256k1 signature of exactly {} bytes",
+                    SIGNATURE_BYTES_LEN
+                )
             }
 
             fn visit_seq<V>(self, mut visitor: V) -> Result<Self::Value, V::Error>
@@ -129,11 +469,28 @@ impl<'de> Deserialize<'de> for Signature {
                 for i in 0..SIGNATURE_BYTES_LEN {
                     signature.0[i] = match visitor.next_element()? {
                         Some(val) => val,
-                        None => return Err(SerdeError::invalid_length(SIGNATURE_BYTES_LEN, &self)),
+                        None => return Err(SerdeError::invalid_length(i, &self)),
                     }
                 }
+                // extra elements would otherwise be silently dropped
+                let mut len = SIGNATURE_BYTES_LEN;
+                while visitor.next_element::<u8>()?.is_some() {
+                    len += 1;
+                }
+                if len != SIGNATURE_BYTES_LEN {
+                    return Err(SerdeError::invalid_length(len, &self));
+                }
                 Ok(signature)
             }
+
+            fn visit_bytes<E>(self, bytes: &[u8]) -> Result<Self::Value, E>
+            where
+                E: SerdeError,
+            {
+                bytes_to_sig(bytes)
+                    .map(Signature)
+                    .map_err(|_| E::invalid_length(bytes.len(), &self))
+            }
         }
 
         let visitor = SignatureVisitor;
@@ -141,6 +498,7 @@ impl<'de> Deserialize<'de> for Signature {
     }
 }
 
+#[cfg(feature = "serde")]
 impl Serialize for Signature {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -161,16 +519,16 @@ impl Eq for Signature {}
 impl fmt::Debug for Signature {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         f.debug_struct("Signature")
-            .field("r", &self.0[0..32].to_hex())
-            .field("s", &self.0[32..64].to_hex())
-            .field("v", &self.0[64..65].to_hex())
+            .field("r", &to_hex(&self.0[0..32]))
+            .field("s", &to_hex(&self.0[32..64]))
+            .field("v", &to_hex(&self.0[64..65]))
             .finish()
     }
 }
 
 impl fmt::Display for Signature {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
-        write!(f, "{}", self.to_hex())
+        write!(f, "{}", to_hex(&self.0))
     }
 }
 
@@ -180,12 +538,41 @@ impl Default for Signature {
     }
 }
 
+// hashes all 65 bytes, consistent with `PartialEq`; use
+// `VByteAgnosticSignature` for keys that should ignore `v`.
 impl Hash for Signature {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.0.hash(state);
     }
 }
 
+/// Signature whose equality and hashing only look at r and s.
+///
+/// Meant as a map key for replay detection, where the same r and s with a
+/// different `v` byte is still the same signature.
+#[derive(Debug, Clone)]
+pub struct VByteAgnosticSignature(pub Signature);
+
+impl PartialEq for VByteAgnosticSignature {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 .0[0..64] == other.0 .0[0..64]
+    }
+}
+
+impl Eq for VByteAgnosticSignature {}
+
+impl Hash for VByteAgnosticSignature {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0 .0[0..64].hash(state);
+    }
+}
+
+impl From<Signature> for VByteAgnosticSignature {
+    fn from(sig: Signature) -> Self {
+        VByteAgnosticSignature(sig)
+    }
+}
+
 impl Clone for Signature {
     fn clone(&self) -> Self {
         Signature(self.0)
@@ -205,11 +592,50 @@ impl From<Signature> for [u8; 65] {
 }
 
 impl<'a> From<&'a [u8]> for Signature {
+    /// Panics if the slice is not 65 bytes long.
     fn from(slice: &'a [u8]) -> Signature {
-        assert_eq!(slice.len(), SIGNATURE_BYTES_LEN);
-        let mut bytes = [0u8; 65];
-        bytes.copy_from_slice(slice);
-        Signature(bytes)
+        Signature(bytes_to_sig(slice).expect("signature must be 65 bytes"))
+    }
+}
+
+impl FromStr for Signature {
+    type Err = Error;
+
+    /// Parse a 130-character hex string, with or without a `0x` prefix.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.strip_prefix("0x").unwrap_or(s);
+        let bytes = from_hex(s).ok_or(Error::InvalidSignature)?;
+        Ok(Signature(bytes_to_sig(&bytes)?))
+    }
+}
+
+impl<'a> TryFrom<&'a str> for Signature {
+    type Error = Error;
+
+    /// Parse either hex or base64.
+    ///
+    /// Input of 130 characters (132 with a `0x` prefix) consisting only of
+    /// hex digits is treated as hex; anything else is decoded as base64.
+    /// The two encodings cannot collide for a valid signature: 130 base64
+    /// characters decode to at least 96 bytes, never to 65.
+    fn try_from(s: &'a str) -> Result<Self, Self::Error> {
+        let hex = s.strip_prefix("0x").unwrap_or(s);
+        if hex.len() == SIGNATURE_BYTES_LEN * 2 && hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return hex.parse();
+        }
+        let bytes = from_base64(s).ok_or(Error::InvalidSignature)?;
+        Ok(Signature(bytes_to_sig(&bytes)?))
+    }
+}
+
+/// r || s without recovery id, e.g. standard ECDSA output; `v` is set to 0.
+///
+/// Set the real recovery id before `recover` or anything else relying on it.
+impl<'a> TryFrom<&'a [u8; 64]> for Signature {
+    type Error = Error;
+
+    fn try_from(rs: &'a [u8; 64]) -> Result<Self, Self::Error> {
+        Signature::from_compact(rs, 0)
     }
 }
 
@@ -219,6 +645,21 @@ impl<'a> From<&'a Signature> for &'a [u8] {
     }
 }
 
+impl AsRef<[u8]> for Signature {
+    fn as_ref(&self) -> &[u8] {
+        &self.0[..]
+    }
+}
+
+impl<'a> IntoIterator for &'a Signature {
+    type Item = &'a u8;
+    type IntoIter = std::slice::Iter<'a, u8>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
 impl fmt::LowerHex for Signature {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         for i in &self.0[..] {
@@ -228,6 +669,15 @@ impl fmt::LowerHex for Signature {
     }
 }
 
+impl fmt::UpperHex for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for i in &self.0[..] {
+            write!(f, "{:02X}", i)?;
+        }
+        Ok(())
+    }
+}
+
 impl From<Signature> for String {
     fn from(s: Signature) -> Self {
         format!("{:x}", s)
@@ -248,204 +698,671 @@ impl DerefMut for Signature {
     }
 }
 
+/// Signature layout negotiated with a peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureFormat {
+    /// r || s, without recovery id.
+    Compact64,
+    /// r || s || v, the native layout.
+    Recoverable65,
+}
+
+/// Encode a signature in the layout a peer expects.
+pub fn encode_for(format: SignatureFormat, sig: &Signature) -> Vec<u8> {
+    match format {
+        SignatureFormat::Compact64 => sig.0[0..64].to_vec(),
+        SignatureFormat::Recoverable65 => sig.0.to_vec(),
+    }
+}
+
+/// Decode a signature received in the given layout.
+///
+/// `Compact64` carries no recovery id, so `v` is set to 0; such signatures
+/// can be verified against a public key but not recovered reliably.
+pub fn decode_for(format: SignatureFormat, bytes: &[u8]) -> Result<Signature, Error> {
+    match format {
+        SignatureFormat::Compact64 if bytes.len() == 64 => Signature::from_any(bytes),
+        SignatureFormat::Recoverable65 => Ok(Signature(bytes_to_sig(bytes)?)),
+        _ => Err(Error::InvalidSignature),
+    }
+}
+
+/// Decode signatures stored back to back as 65-byte chunks.
+///
+/// A trailing partial chunk is yielded as an error after the complete ones.
+pub fn iter_signatures(bytes: &[u8]) -> impl Iterator<Item = Result<Signature, Error>> + '_ {
+    bytes
+        .chunks(SIGNATURE_BYTES_LEN)
+        .map(|chunk| bytes_to_sig(chunk).map(Signature))
+}
+
+/// Parse the compact signature without copying it out of the backing array.
+#[cfg(feature = "secp256k1")]
+pub(crate) fn to_recoverable(signature: &Signature) -> Result<RecoverableSignature, Error> {
+    let rec_id = RecoveryId::from_i32(i32::from(signature.0[64]))?;
+    Ok(RecoverableSignature::from_compact(
+        &signature.0[0..64],
+        rec_id,
+    )?)
+}
+
+/// Parse our 64-byte public key into a secp256k1 public key.
+///
+/// Only with the `secp256k1` feature, like everything using its types.
+///
+/// libsecp256k1 only parses tagged keys, so the 64 bytes are copied behind a
+/// 0x04 prefix; the buffer lives on the stack, no allocation is involved.
+/// The copy can not be avoided through the C API, build a `Verifier` to pay
+/// for it once when the same key verifies many signatures.
+#[cfg(feature = "secp256k1")]
+pub fn to_secp_pubkey(pubkey: &PubKey) -> Result<PublicKey, Error> {
+    let mut pdata = [4u8; 65];
+    pdata[1..65].copy_from_slice(pubkey.as_bytes());
+    Ok(PublicKey::from_slice(&pdata)?)
+}
+
+/// Strip the tag byte of an uncompressed secp256k1 public key.
+///
+/// The length is pinned by the annotated array type and the tag is checked,
+/// so a layout change in `secp256k1` can not silently shift the key bytes.
+#[cfg(feature = "secp256k1")]
+pub fn from_secp_pubkey(public_key: &PublicKey) -> PubKey {
+    let serialized: [u8; PUBKEY_BYTES_LEN + 1] = public_key.serialize_uncompressed();
+    assert_eq!(serialized[0], 0x04, "untagged uncompressed public key");
+    PubKey::from_slice(&serialized[1..])
+}
+
+/// Serialize a public key in the 33-byte compressed form.
+pub fn to_compressed(pubkey: &PubKey) -> Result<[u8; 33], Error> {
+    DefaultBackend::parse_pubkey(pubkey)?;
+    Ok(compress(pubkey))
+}
+
+/// Compress a key known to be on the curve: the parity of y, then x.
+fn compress(pubkey: &PubKey) -> [u8; 33] {
+    let mut compressed = [0u8; 33];
+    compressed[0] = 0x02 | (pubkey.0[63] & 1);
+    compressed[1..33].copy_from_slice(&pubkey.0[0..32]);
+    compressed
+}
+
+/// Parse a public key from the 33-byte compressed form.
+///
+/// `PubKey` only ever holds the untagged uncompressed coordinates, so keys
+/// parsed from either form compare and hash the same.
+pub fn from_compressed(bytes: &[u8; 33]) -> Result<PubKey, Error> {
+    DefaultBackend::decompress(bytes)
+}
+
+/// Access a `PubKey` as a secp256k1 public key, e.g. for point arithmetic.
+#[cfg(feature = "secp256k1")]
+pub trait AsSecpPubKey {
+    fn as_secp_pubkey(&self) -> Result<PublicKey, Error>;
+}
+
+#[cfg(feature = "secp256k1")]
+impl AsSecpPubKey for PubKey {
+    fn as_secp_pubkey(&self) -> Result<PublicKey, Error> {
+        to_secp_pubkey(self)
+    }
+}
+
 pub fn sign(privkey: &PrivKey, message: &Message) -> Result<Signature, Error> {
-    let context = &SECP256K1;
-    // no way to create from raw byte array.
-    let sec: &SecretKey = unsafe { &*(privkey as *const H256 as *const secp256k1::SecretKey) };
-    let s = context.sign_ecdsa_recoverable(&SecpMessage::from_slice(&message.0[..])?, sec);
-    let (rec_id, data) = s.serialize_compact();
-    let mut data_arr = [0; 65];
+    DefaultBackend::sign(privkey, message)
+}
+
+/// Hash `data` with the caller's hash function, then sign the digest.
+pub fn sign_with_hasher<F: FnOnce(&[u8]) -> H256>(
+    privkey: &PrivKey,
+    data: &[u8],
+    hasher: F,
+) -> Result<Signature, Error> {
+    sign(privkey, &Message::from_digest(hasher(data)))
+}
+
+/// Sign with `aux_rand` mixed into the RFC 6979 nonce derivation.
+///
+/// A fault injected into deterministic signing can leak the key by making
+/// two signatures share a nonce; fresh randomness on every call removes the
+/// repeat. Both backends derive the same nonce, so the result does not
+/// depend on which one is selected.
+pub fn sign_hedged(
+    privkey: &PrivKey,
+    message: &Message,
+    aux_rand: &[u8; 32],
+) -> Result<Signature, Error> {
+    DefaultBackend::sign_hedged(privkey, message, aux_rand)
+}
+
+/// Check a big-endian scalar is a valid private key: non zero and below the order.
+pub(crate) fn is_valid_privkey_scalar(value: &[u8; 32]) -> bool {
+    let (_, borrow) = sub_be(value, &CURVE_ORDER);
+    borrow != 0 && value.iter().any(|b| *b != 0)
+}
+
+/// Reduce a big-endian 256-bit value modulo the group order.
+///
+/// Values of 256 bits are below 2n, so one subtraction suffices.
+#[cfg(any(feature = "secp256k1", feature = "arbitrary"))]
+pub(crate) fn reduce_scalar(value: &[u8; 32]) -> ([u8; 32], bool) {
+    let (reduced, borrow) = sub_be(value, &CURVE_ORDER);
+    if borrow == 0 {
+        (reduced, true)
+    } else {
+        (*value, false)
+    }
+}
+
+/// Invert a scalar modulo the group order as `x^(n-2)`, n being prime.
+///
+/// Not constant time, never use it on a secret that is still in use.
+#[cfg(feature = "secp256k1")]
+pub(crate) fn invert_scalar(x: &SecretKey) -> SecretKey {
+    let mut exp = CURVE_ORDER;
+    exp[31] -= 2;
+    let mut acc: Option<SecretKey> = None;
+    for byte in exp.iter() {
+        for bit in (0..8).rev() {
+            // Products of non-zero scalars modulo a prime are never zero.
+            if let Some(a) = acc {
+                acc = Some(a.mul_tweak(&Scalar::from(a)).expect("non-zero product"));
+            }
+            if (byte >> bit) & 1 == 1 {
+                acc = Some(match acc {
+                    Some(a) => a.mul_tweak(&Scalar::from(*x)).expect("non-zero product"),
+                    None => *x,
+                });
+            }
+        }
+    }
+    acc.expect("exponent is non-zero")
+}
+
+/// Sign with a caller-chosen nonce `k`, to reproduce published ECDSA vectors.
+///
+/// The result is normalized to low `s` like every signature `sign` produces.
+/// Reusing or leaking `k` reveals the private key, so this is only compiled
+/// for this crate's own tests and never exported.
+#[cfg(all(test, feature = "secp256k1"))]
+pub(crate) fn sign_with_nonce(
+    privkey: &PrivKey,
+    message: &Message,
+    k: &[u8; 32],
+) -> Result<Signature, Error> {
+    let d = SecretKey::from_slice(&privkey.0)?;
+    let nonce = SecretKey::from_slice(k)?;
+    let point = PublicKey::from_secret_key(&SECP256K1, &nonce).serialize_uncompressed();
+
+    let mut x = [0u8; 32];
+    x.copy_from_slice(&point[1..33]);
+    let (r, overflowed) = reduce_scalar(&x);
+    let mut recovery_id = (point[64] & 1) | if overflowed { 2 } else { 0 };
 
-    // no need to check if s is low, it always is
-    data_arr[0..64].copy_from_slice(&data[0..64]);
-    data_arr[64] = rec_id.to_i32() as u8;
-    Ok(Signature(data_arr))
+    let r_scalar = Scalar::from_be_bytes(r).map_err(|_| Error::ROutOfRange)?;
+    let z = Scalar::from_be_bytes(reduce_scalar(message.as_fixed_bytes()).0)
+        .map_err(|_| Error::InvalidMessage)?;
+    // s = k^-1 * (z + r * d)
+    let sum = d.mul_tweak(&r_scalar)?.add_tweak(&z)?;
+    let mut s = invert_scalar(&nonce).mul_tweak(&Scalar::from(sum))?;
+    if s.secret_bytes() > HALF_CURVE_ORDER {
+        s = s.negate();
+        recovery_id ^= 1;
+    }
+
+    let mut sig = [0u8; 65];
+    sig[0..32].copy_from_slice(&r);
+    sig[32..64].copy_from_slice(&s.secret_bytes());
+    sig[64] = recovery_id;
+    Ok(Signature(sig))
+}
+
+/// Sign and check the signature recovers to the signer's own address.
+///
+/// Guards against corrupted keys or faulty hardware producing bad signatures.
+pub fn sign_verified(privkey: &PrivKey, message: &Message) -> Result<(Signature, Address), Error> {
+    let (_, address) = derive_identity(privkey)?;
+    let signature = sign(privkey, message)?;
+    if pubkey_to_address(&recover(&signature, message)?) != address {
+        return Err(Error::Unexpected(
+            "signature does not recover to the signing key".into(),
+        ));
+    }
+    Ok((signature, address))
 }
 
+/// Verify against a known public key.
+///
+/// Prefer this over `verify_address` whenever the signer's public key is at
+/// hand: it is a single verification, while `verify_address` has to recover
+/// the public key, which costs more than verifying, and then hash it into an
+/// address. The key is parsed by the same `to_secp_pubkey` helper as
+/// everywhere else, use `Verifier` to parse it once for many signatures.
 pub fn verify_public(
     pubkey: &PubKey,
     signature: &Signature,
     message: &Message,
 ) -> Result<bool, Error> {
-    let context = &SECP256K1;
-    let rsig = RecoverableSignature::from_compact(
-        &signature[0..64],
-        RecoveryId::from_i32(i32::from(signature[64]))?,
-    )?;
-    let sig = rsig.to_standard();
-
-    let pdata: [u8; 65] = {
-        let mut temp = [4u8; 65];
-        temp[1..65].copy_from_slice(pubkey.as_bytes());
-        temp
-    };
-
-    let public_key = PublicKey::from_slice(&pdata)?;
-    match context.verify_ecdsa(&SecpMessage::from_slice(&message.0[..])?, &sig, &public_key) {
-        Ok(_) => Ok(true),
-        Err(SecpError::IncorrectSignature) => Ok(false),
-        Err(x) => Err(Error::from(x)),
-    }
+    DefaultBackend::verify(pubkey, signature, message)
 }
 
-pub fn verify_address(
-    address: &Address,
+/// Verify against an already parsed `secp256k1` public key, e.g. one from ECDH.
+///
+/// Skips serializing the key to a `PubKey` only to parse it again; always
+/// goes through libsecp256k1, whichever backend is selected, and is only
+/// there with the `secp256k1` feature.
+#[cfg(feature = "secp256k1")]
+pub fn verify_with_secp_pubkey(
+    pubkey: &PublicKey,
     signature: &Signature,
     message: &Message,
 ) -> Result<bool, Error> {
-    let pubkey = recover(signature, message)?;
-    let recovered_address = pubkey_to_address(&pubkey);
-    Ok(address == &recovered_address)
+    Secp256k1Backend::verify_parsed(pubkey, signature, message)
 }
 
-pub fn recover(signature: &Signature, message: &Message) -> Result<PubKey, Error> {
-    let context = &SECP256K1;
-    let rsig = RecoverableSignature::from_compact(
-        &signature[0..64],
-        RecoveryId::from_i32(i32::from(signature[64]))?,
-    )?;
-    let publ = context.recover_ecdsa(&SecpMessage::from_slice(&message.0[..])?, &rsig)?;
-    let serialized = publ.serialize_uncompressed();
-
-    let mut pubkey = PubKey::default();
-    pubkey.0.copy_from_slice(&serialized[1..65]);
-    Ok(pubkey)
+/// Hash `data` with the caller's hash function, then verify the digest.
+///
+/// Use the same hasher as `sign_with_hasher`; any other gives `Ok(false)`.
+pub fn verify_public_bytes<F: FnOnce(&[u8]) -> H256>(
+    pubkey: &PubKey,
+    signature: &Signature,
+    data: &[u8],
+    hasher: F,
+) -> Result<bool, Error> {
+    verify_public(pubkey, signature, &Message::from_digest(hasher(data)))
 }
 
-impl Sign for Signature {
-    type PrivKey = PrivKey;
-    type PubKey = PubKey;
-    type Message = Message;
-    type Error = Error;
-    type Address = Address;
-
-    fn sign(privkey: &Self::PrivKey, message: &Self::Message) -> Result<Self, Self::Error> {
-        let context = &SECP256K1;
-        // no way to create from raw byte array.
-        let sec: &SecretKey = unsafe { &*(privkey as *const H256 as *const secp256k1::SecretKey) };
-        let msg = SecpMessage::from_slice(&message.0[..]).unwrap();
-        let s = context.sign_ecdsa_recoverable(&msg, sec);
-        let (rec_id, data) = s.serialize_compact();
-        let mut data_arr = [0; 65];
-
-        // no need to check if s is low, it always is
-        data_arr[0..64].copy_from_slice(&data[0..64]);
-        data_arr[64] = rec_id.to_i32() as u8;
-        Ok(Signature(data_arr))
+/// Verify, but only if `message` is the hash the protocol committed to.
+///
+/// A mismatch gives `Ok(false)` without any signature check.
+pub fn verify_with_commitment(
+    pubkey: &PubKey,
+    signature: &Signature,
+    message: &Message,
+    expected_hash: &H256,
+) -> Result<bool, Error> {
+    if message.as_fixed_bytes() != expected_hash.as_fixed_bytes() {
+        return Ok(false);
     }
+    verify_public(pubkey, signature, message)
+}
 
-    fn recover(&self, message: &Message) -> Result<Self::PubKey, Error> {
-        let context = &SECP256K1;
-        let rsig = RecoverableSignature::from_compact(
-            &self.0[0..64],
-            RecoveryId::from_i32(i32::from(self.0[64]))?,
-        )?;
-        let publ = context.recover_ecdsa(&SecpMessage::from_slice(&message.0[..])?, &rsig)?;
-        let serialized = publ.serialize_uncompressed();
+/// Detailed result of `verify_public_detailed`.
+#[derive(Debug)]
+pub enum VerifyOutcome {
+    Valid,
+    /// Well formed signature, but not made by this key over this message.
+    WrongSigner,
+    /// The signature itself is malformed, the reason is the failing component.
+    Malformed(Error),
+    /// Verification could not be carried out, e.g. the public key is invalid.
+    Error(Error),
+}
 
-        let mut pubkey = PubKey::default();
-        pubkey.0.copy_from_slice(&serialized[1..65]);
-        Ok(pubkey)
+/// Verify, telling malformed signatures apart from wrong signers and errors.
+///
+/// High `s` values count as malformed since they are never accepted.
+pub fn verify_public_detailed(
+    pubkey: &PubKey,
+    signature: &Signature,
+    message: &Message,
+) -> VerifyOutcome {
+    if let Err(reason) = signature.validate() {
+        return VerifyOutcome::Malformed(reason);
     }
+    if !signature.is_low_s() {
+        return VerifyOutcome::Malformed(Error::SOutOfRange);
+    }
+    match verify_public(pubkey, signature, message) {
+        Ok(true) => VerifyOutcome::Valid,
+        Ok(false) => VerifyOutcome::WrongSigner,
+        Err(e) => VerifyOutcome::Error(e),
+    }
+}
 
-    fn verify_public(
-        &self,
-        pubkey: &Self::PubKey,
-        message: &Self::Message,
-    ) -> Result<bool, Self::Error> {
-        let context = &SECP256K1;
-        let rsig = RecoverableSignature::from_compact(
-            &self.0[0..64],
-            RecoveryId::from_i32(i32::from(self.0[64]))?,
-        )?;
-        let sig = rsig.to_standard();
-
-        let pdata: [u8; 65] = {
-            let mut temp = [4u8; 65];
-            temp[1..65].copy_from_slice(pubkey.as_bytes());
-            temp
-        };
-
-        let publ = PublicKey::from_slice(&pdata)?;
-        match context.verify_ecdsa(&SecpMessage::from_slice(&message.0[..])?, &sig, &publ) {
-            Ok(_) => Ok(true),
-            Err(SecpError::IncorrectSignature) => Ok(false),
-            Err(x) => Err(Error::from(x)),
+/// Verify a sequence of messages signed by the same key, parsing the key once.
+///
+/// Returns `Ok(true)` only if every pair verifies, stopping at the first failure.
+pub fn verify_public_many(pubkey: &PubKey, pairs: &[(Signature, Message)]) -> Result<bool, Error> {
+    let public_key = DefaultBackend::parse_pubkey(pubkey)?;
+    for (signature, message) in pairs {
+        if !DefaultBackend::verify_parsed(&public_key, signature, message)? {
+            return Ok(false);
         }
     }
+    Ok(true)
+}
 
-    fn verify_address(
-        &self,
-        address: &Address,
-        message: &Self::Message,
-    ) -> Result<bool, Self::Error> {
-        let pubkey = self.recover(message)?;
-        let recovered_address = pubkey_to_address(&pubkey);
-        Ok(address == &recovered_address)
+/// Verify, turning a wrong signer into `Error::InvalidSignature`.
+///
+/// Fits `?` and `collect::<Result<Vec<_>, _>>()`, which stop at the first
+/// failing entry.
+pub fn verify_or_err(
+    pubkey: &PubKey,
+    signature: &Signature,
+    message: &Message,
+) -> Result<(), Error> {
+    if verify_public(pubkey, signature, message)? {
+        Ok(())
+    } else {
+        Err(Error::InvalidSignature)
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::super::KeyPair;
-    use super::{PrivKey, Signature};
-    use bincode::{deserialize, serialize};
-    use cita_crypto_trait::{CreateKey, Sign};
-    use cita_types::H256;
-    use hashable::Hashable;
-    use std::str::FromStr;
+/// Verify every entry, stopping at the first one that does not verify.
+///
+/// Fails with the index of that entry and `Error::InvalidSignature`, or the
+/// error verification itself ran into.
+pub fn verify_batch_strict(items: &[(PubKey, Signature, Message)]) -> Result<(), (usize, Error)> {
+    for (i, (pubkey, signature, message)) in items.iter().enumerate() {
+        verify_or_err(pubkey, signature, message).map_err(|e| (i, e))?;
+    }
+    Ok(())
+}
 
-    #[test]
-    fn test_sign_verify() {
-        let keypair = KeyPair::gen_keypair();
-        let str = "".to_owned();
-        let message = str.crypt_hash();
-        let sig = Signature::sign(keypair.privkey(), &message.into()).unwrap();
-        assert!(sig
-            .verify_public(keypair.pubkey(), &message.into())
-            .unwrap());
+/// Verify entries in order until `max` has elapsed.
+///
+/// Entries not reached get `None`; an entry that fails to verify, or errors,
+/// gets `Some(false)`. Also returns how many entries were verified, which
+/// is the index to resume from. The budget is checked between entries, so
+/// it can be overrun by at most one verification.
+pub fn verify_batch_budgeted(
+    items: &[(PubKey, Signature, Message)],
+    max: Duration,
+) -> (Vec<Option<bool>>, usize) {
+    let start = Instant::now();
+    let mut results = vec![None; items.len()];
+    for (i, (pubkey, signature, message)) in items.iter().enumerate() {
+        if start.elapsed() >= max {
+            return (results, i);
+        }
+        results[i] = Some(verify_public(pubkey, signature, message).unwrap_or(false));
+    }
+    (results, items.len())
+}
+
+/// Verify against an address, for callers that only know the signer's address.
+///
+/// This recovers the public key and hashes it, see `verify_public` for the
+/// cheaper path when the public key is known.
+pub fn verify_address(
+    address: &Address,
+    signature: &Signature,
+    message: &Message,
+) -> Result<bool, Error> {
+    // skip the recovery for signatures that can't be valid anyway
+    if !signature.is_valid() {
+        return Ok(false);
+    }
+    let pubkey = recover(signature, message).map_err(|e| e.context("verify_address"))?;
+    let recovered_address = pubkey_to_address(&pubkey);
+    Ok(addresses_ct_eq(address, &recovered_address))
+}
+
+/// Like `verify_address`, but a signature recovering to the zero address is an error.
+///
+/// No key is known to hash to the zero address, so such a signature was
+/// crafted; access control treating the zero address as "nobody" must not
+/// accept it as a signer. Fails with `InvalidAddress`.
+pub fn verify_address_strict(
+    address: &Address,
+    signature: &Signature,
+    message: &Message,
+) -> Result<bool, Error> {
+    if !signature.is_valid() {
+        return Ok(false);
+    }
+    let pubkey = recover(signature, message).map_err(|e| e.context("verify_address_strict"))?;
+    match_nonzero_signer(address, &pubkey_to_address(&pubkey))
+}
+
+fn match_nonzero_signer(address: &Address, recovered: &Address) -> Result<bool, Error> {
+    if recovered.is_zero() {
+        return Err(Error::InvalidAddress);
+    }
+    Ok(addresses_ct_eq(address, recovered))
+}
+
+/// Recover the signer once and look it up in an allowlist.
+///
+/// Returns the matching address, or `None` for signers outside the list and,
+/// like `verify_address`, for out of range signatures.
+pub fn verify_against_set(
+    allow: &HashSet<Address>,
+    sig: &Signature,
+    msg: &Message,
+) -> Result<Option<Address>, Error> {
+    if !sig.is_valid() {
+        return Ok(None);
+    }
+    let address = pubkey_to_address(&recover(sig, msg)?);
+    Ok(allow.get(&address).copied())
+}
+
+/// Mark which validators signed `message`, by index in `validators`.
+///
+/// Like `verify_address`, out of range signatures are skipped rather than
+/// failing the whole certificate, as are unrecoverable signatures and
+/// signatures by non-validators.
+pub fn recover_signer_bitmap(
+    sigs: &[Signature],
+    message: &Message,
+    validators: &[Address],
+) -> Result<Vec<bool>, Error> {
+    let index: HashMap<&Address, usize> = validators.iter().zip(0..).collect();
+    let mut bitmap = vec![false; validators.len()];
+    for signature in sigs.iter().filter(|sig| sig.is_valid()) {
+        // in range but unrecoverable, e.g. an r with no curve point
+        let pubkey = match recover(signature, message) {
+            Ok(pubkey) => pubkey,
+            Err(_) => continue,
+        };
+        if let Some(&i) = index.get(&pubkey_to_address(&pubkey)) {
+            bitmap[i] = true;
+        }
+    }
+    Ok(bitmap)
+}
+
+pub fn recover(signature: &Signature, message: &Message) -> Result<PubKey, Error> {
+    DefaultBackend::recover(signature, message)
+}
+
+/// Recover the public key of a signature over a raw 32-byte digest.
+pub fn recover_h256(signature: &Signature, digest: &H256) -> Result<PubKey, Error> {
+    recover(signature, &Message::from_digest(*digest))
+}
+
+/// Recover the signer address of every pair, stopping at the first error.
+pub fn recover_addresses(pairs: &[(Signature, Message)]) -> Result<Vec<Address>, Error> {
+    pairs
+        .iter()
+        .map(|(signature, message)| recover(signature, message).map(|p| pubkey_to_address(&p)))
+        .collect()
+}
+
+/// Recover the public key straight into the 33-byte compressed form.
+pub fn recover_compressed(signature: &Signature, message: &Message) -> Result<[u8; 33], Error> {
+    Ok(compress(&recover(signature, message)?))
+}
+
+/// Recover the public key, refusing the all-zero message with `InvalidMessage`.
+///
+/// Catches a `Message::default()` that was signed in place of a real digest.
+pub fn recover_nonzero(signature: &Signature, message: &Message) -> Result<PubKey, Error> {
+    if message.is_zero() {
+        return Err(Error::InvalidMessage);
+    }
+    recover(signature, message)
+}
+
+/// Recover the public key of a signature from an untrusted source.
+///
+/// Unlike `recover`, the signature must pass `validate` first, which also
+/// limits `v` to 0 or 1.
+pub fn recover_untrusted(signature: &Signature, message: &Message) -> Result<PubKey, Error> {
+    signature.validate()?;
+    recover(signature, message)
+}
+
+/// Recover the public key, telling structural errors apart from failed recovery.
+///
+/// Out of range r/s, or a `v` that is no recovery id at all, give
+/// `InvalidSignature`; a well formed signature no public key can be recovered
+/// from, e.g. because of a wrong recovery id, gives `Unrecoverable`.
+pub fn recover_checked(signature: &Signature, message: &Message) -> Result<PubKey, Error> {
+    match signature.validate() {
+        Ok(()) | Err(Error::VOutOfRange) if signature.v() <= 3 => {}
+        _ => return Err(Error::InvalidSignature),
+    }
+    recover(signature, message).map_err(|_| Error::Unrecoverable)
+}
+
+/// Recover the public key, also accepting the legacy 27/28 encoding of `v`.
+///
+/// `v` is read as `RecoveryConvention::Raw` below 27 and as
+/// `RecoveryConvention::Legacy` from there on, so only 0, 1, 27 and 28 are
+/// accepted.
+pub fn recover_lenient(signature: &Signature, message: &Message) -> Result<PubKey, Error> {
+    let v = u64::from(signature.v());
+    let convention = if v >= 27 {
+        RecoveryConvention::Legacy
+    } else {
+        RecoveryConvention::Raw
+    };
+    let extended = ExtendedSignature::new(
+        H256::from_slice(signature.r()),
+        H256::from_slice(signature.s()),
+        v,
+    );
+    recover_with_convention(&extended, convention, message)
+}
+
+impl Sign for Signature {
+    type PrivKey = PrivKey;
+    type PubKey = PubKey;
+    type Message = Message;
+    type Error = Error;
+    type Address = Address;
+
+    fn sign(privkey: &Self::PrivKey, message: &Self::Message) -> Result<Self, Self::Error> {
+        sign(privkey, message)
+    }
+
+    fn recover(&self, message: &Message) -> Result<Self::PubKey, Error> {
+        recover(self, message)
+    }
+
+    fn verify_public(
+        &self,
+        pubkey: &Self::PubKey,
+        message: &Self::Message,
+    ) -> Result<bool, Self::Error> {
+        verify_public(pubkey, self, message)
+    }
+
+    fn verify_address(
+        &self,
+        address: &Address,
+        message: &Self::Message,
+    ) -> Result<bool, Self::Error> {
+        verify_address(address, self, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::KeyPair;
+    use super::{
+        bytes_to_sig, decode_for, encode_for, from_compressed, iter_signatures,
+        match_nonzero_signer, recover, recover_addresses, recover_checked, recover_compressed,
+        recover_h256, recover_lenient, recover_nonzero, recover_signer_bitmap, recover_untrusted,
+        sign_hedged, sign_verified, sign_with_hasher, to_compressed, verify_address_strict,
+        verify_against_set, verify_batch_budgeted, verify_batch_strict, verify_or_err,
+        verify_public_bytes, verify_public_detailed, verify_public_many, verify_with_commitment,
+        Address, Error, Message, PrivKey, PubKey, Signature, SignatureFormat,
+        VByteAgnosticSignature, VerifyOutcome,
+    };
+    #[cfg(feature = "secp256k1")]
+    use super::{
+        from_secp_pubkey, sign_with_nonce, to_secp_pubkey, verify_with_secp_pubkey, AsSecpPubKey,
+        PublicKey,
+    };
+    use crate::encoding::{from_base64, from_hex, to_hex};
+    use crate::keccak256;
+    #[cfg(feature = "serde")]
+    use bincode::{deserialize, serialize};
+    use cita_crypto_trait::{CreateKey, Sign};
+    use cita_types::H256;
+    use std::convert::{TryFrom, TryInto};
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    #[test]
+    fn test_sign_verify() {
+        let keypair = KeyPair::gen_keypair();
+        let str = "".to_owned();
+        let message = Message::hash(str);
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        assert!(sig.verify_public(keypair.pubkey(), &message).unwrap());
     }
 
     #[test]
     fn test_verify_address() {
         let keypair = KeyPair::gen_keypair();
         let str = "".to_owned();
-        let message = str.crypt_hash();
-        let sig = Signature::sign(keypair.privkey(), &message.into()).unwrap();
-        assert_eq!(keypair.pubkey(), &sig.recover(&message.into()).unwrap());
+        let message = Message::hash(str);
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        assert_eq!(keypair.pubkey(), &sig.recover(&message).unwrap());
     }
 
     #[test]
     fn test_recover() {
         let keypair = KeyPair::gen_keypair();
         let str = "".to_owned();
-        let message = str.crypt_hash();
-        let sig = Signature::sign(keypair.privkey(), &message.into()).unwrap();
-        assert_eq!(keypair.pubkey(), &sig.recover(&message.into()).unwrap());
+        let message = Message::hash(str);
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        assert_eq!(keypair.pubkey(), &sig.recover(&message).unwrap());
     }
 
     #[test]
     fn test_into_slice() {
         let keypair = KeyPair::gen_keypair();
         let str = "".to_owned();
-        let message = str.crypt_hash();
-        let sig = Signature::sign(keypair.privkey(), &message.into()).unwrap();
+        let message = Message::hash(str);
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
         let sig = &sig;
         let slice: &[u8] = sig.into();
         assert_eq!(Signature::from(slice), *sig);
     }
 
+    #[cfg(feature = "serde")]
     #[test]
     fn test_de_serialize() {
         let keypair = KeyPair::gen_keypair();
         let str = "".to_owned();
-        let message = str.crypt_hash();
-        let signature = Signature::sign(keypair.privkey().into(), &message.into()).unwrap();
+        let message = Message::hash(str);
+        let signature = Signature::sign(keypair.privkey(), &message).unwrap();
         let se_result = serialize(&signature).unwrap();
         let de_result: Signature = deserialize(&se_result).unwrap();
         assert_eq!(signature, de_result);
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize_wrong_length() {
+        let json = |len: usize| format!("[{}]", vec!["1"; len].join(","));
+
+        let sig: Signature = serde_json::from_str(&json(65)).unwrap();
+        assert_eq!(sig, Signature([1u8; 65]));
+
+        let err = serde_json::from_str::<Signature>(&json(64)).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("invalid length 64, expected secp256k1 signature of exactly 65 bytes"));
+        let err = serde_json::from_str::<Signature>(&json(66)).unwrap_err();
+        assert!(err.to_string().starts_with("invalid length 66"));
+    }
+
     #[test]
     fn test_show_signature() {
         let sk = PrivKey::from(
@@ -453,9 +1370,1083 @@ mod tests {
                 .unwrap(),
         );
         let str = "".to_owned();
-        let message = str.crypt_hash();
+        let message = Message::hash(str);
         println!("message {:?}", message);
-        let signature = Signature::sign(&sk, &message.into()).unwrap();
+        let signature = Signature::sign(&sk, &message).unwrap();
         println!("signature {:?}", signature);
     }
+
+    #[test]
+    fn test_recover_lenient() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        let mut legacy = sig.clone();
+        legacy.0[64] += 27;
+
+        assert_eq!(keypair.pubkey(), &recover_lenient(&sig, &message).unwrap());
+        assert_eq!(
+            keypair.pubkey(),
+            &recover_lenient(&legacy, &message).unwrap()
+        );
+        assert!(legacy.recover(&message).is_err());
+
+        let mut out_of_range = sig;
+        out_of_range.0[64] = 29;
+        assert!(matches!(
+            recover_lenient(&out_of_range, &message),
+            Err(Error::VOutOfRange)
+        ));
+    }
+
+    #[test]
+    fn test_from_any() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        let mut no_v = sig.clone();
+        no_v.0[64] = 0;
+
+        assert_eq!(Signature::from_any(&sig.0).unwrap(), sig);
+        assert_eq!(Signature::from_any(&sig.0[0..64]).unwrap(), no_v);
+
+        #[cfg(feature = "secp256k1")]
+        {
+            let der = secp256k1::ecdsa::Signature::from_compact(&sig.0[0..64])
+                .unwrap()
+                .serialize_der();
+            assert_eq!(Signature::from_any(&der).unwrap(), no_v);
+        }
+        assert!(Signature::from_any(&[0u8; 70]).is_err());
+    }
+
+    #[cfg(feature = "secp256k1")]
+    #[test]
+    fn test_as_secp_pubkey() {
+        let keypair = KeyPair::gen_keypair();
+        let public_key = keypair.pubkey().as_secp_pubkey().unwrap();
+        assert_eq!(public_key, to_secp_pubkey(keypair.pubkey()).unwrap());
+        assert_eq!(&from_secp_pubkey(&public_key), keypair.pubkey());
+    }
+
+    #[test]
+    fn test_verify_address_invalid() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let mut sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        sig.0[0..32].copy_from_slice(&[0u8; 32]);
+        assert!(!sig.verify_address(&keypair.address(), &message).unwrap());
+    }
+
+    #[test]
+    fn test_verify_address_error_context() {
+        // in range, but no curve point has x = 5
+        let sig = Signature::from_rsv(&H256::from_low_u64_be(5), &H256::from_low_u64_be(1), 0);
+        let message = Message::hash("");
+        let err = sig
+            .verify_address(&KeyPair::gen_keypair().address(), &message)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::Context("verify_address", ref inner) if matches!(**inner, Error::InvalidSignature)
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Crypto error (verify_address: Invalid EC signature)"
+        );
+    }
+
+    #[test]
+    fn test_as_ref() {
+        fn byte_len<T: AsRef<[u8]>>(bytes: T) -> usize {
+            bytes.as_ref().len()
+        }
+
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        assert_eq!(sig.as_ref(), &sig.0[..]);
+        assert_eq!(byte_len(&sig), 65);
+        // PubKey and Address get it from the underlying hash types
+        assert_eq!(byte_len(keypair.pubkey()), 64);
+        assert_eq!(byte_len(keypair.address()), 20);
+    }
+
+    #[test]
+    fn test_recover_untrusted() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let mut sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        assert_eq!(
+            keypair.pubkey(),
+            &recover_untrusted(&sig, &message).unwrap()
+        );
+
+        sig.0[64] = 200;
+        assert!(matches!(
+            recover_untrusted(&sig, &message),
+            Err(Error::VOutOfRange)
+        ));
+    }
+
+    #[test]
+    fn test_bytes_to_sig() {
+        assert!(bytes_to_sig(&[]).is_err());
+        assert!(bytes_to_sig(&[1u8; 64]).is_err());
+        assert!(bytes_to_sig(&[1u8; 66]).is_err());
+        assert_eq!(bytes_to_sig(&[1u8; 65]).unwrap(), [1u8; 65]);
+    }
+
+    #[cfg(feature = "rlp")]
+    #[test]
+    fn test_rlp_wrong_length() {
+        let encoded = rlp::encode(&vec![0u8; 64]);
+        assert!(rlp::decode::<Signature>(&encoded).is_err());
+
+        let sig = Signature([7u8; 65]);
+        assert_eq!(rlp::decode::<Signature>(&rlp::encode(&sig)).unwrap(), sig);
+    }
+
+    #[test]
+    fn test_verify_public_many() {
+        let keypair = KeyPair::gen_keypair();
+        let mut pairs: Vec<_> = ["a", "b", "c"]
+            .iter()
+            .map(|data| {
+                let message = Message::hash(data);
+                (
+                    Signature::sign(keypair.privkey(), &message).unwrap(),
+                    message,
+                )
+            })
+            .collect();
+        assert!(verify_public_many(keypair.pubkey(), &pairs).unwrap());
+        assert!(verify_public_many(keypair.pubkey(), &[]).unwrap());
+
+        pairs[1].1 = Message::hash("d");
+        assert!(!verify_public_many(keypair.pubkey(), &pairs).unwrap());
+    }
+
+    #[test]
+    fn test_verify_batch_strict() {
+        let mut items: Vec<_> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|data| {
+                let keypair = KeyPair::gen_keypair();
+                let message = Message::hash(data);
+                let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+                (*keypair.pubkey(), sig, message)
+            })
+            .collect();
+        assert!(verify_batch_strict(&items).is_ok());
+        assert!(verify_batch_strict(&[]).is_ok());
+
+        items[2].2 = Message::hash("e");
+        items[3].0 = PubKey::zero();
+        match verify_batch_strict(&items) {
+            Err((2, Error::InvalidSignature)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_verify_batch_budgeted() {
+        let mut items: Vec<_> = (0..50)
+            .map(|i: u32| {
+                let keypair = KeyPair::gen_keypair();
+                let message = Message::hash(i.to_string());
+                let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+                (*keypair.pubkey(), sig, message)
+            })
+            .collect();
+        items[1].2 = Message::hash("other");
+
+        let (results, completed) = verify_batch_budgeted(&items, Duration::from_secs(60));
+        assert_eq!(completed, 50);
+        assert_eq!(results[0], Some(true));
+        assert_eq!(results[1], Some(false));
+        assert!(results[2..].iter().all(|r| *r == Some(true)));
+
+        let (results, completed) = verify_batch_budgeted(&items, Duration::from_micros(1));
+        assert!(completed < 50);
+        assert!(results[..completed].iter().all(Option::is_some));
+        assert!(results[completed..].iter().all(Option::is_none));
+
+        let (results, completed) = verify_batch_budgeted(&items, Duration::from_secs(0));
+        assert_eq!(completed, 0);
+        assert!(results.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_versioned_bytes() {
+        let sk = PrivKey::from(
+            H256::from_str("80762b900f072d199e35ea9b1ee0e2e631a87762f8855b32d4ec13e37a3a65c1")
+                .unwrap(),
+        );
+        let message = Message::from_digest(H256::from_low_u64_be(1));
+        let sig = Signature::sign(&sk, &message).unwrap();
+
+        // the stored format must never change
+        let bytes = sig.to_versioned_bytes();
+        assert_eq!(
+            to_hex(&bytes),
+            "0136ccda2ac7b9452b797c5022b4b972b6899fb2014963b380b5b0d58d3f23807f3372576eb91a73\
+             3c8aa7d57d198e72669eaa4475bbb9e266a69287bfbe74e51301"
+        );
+        assert_eq!(Signature::from_versioned_bytes(&bytes).unwrap(), sig);
+
+        let mut future = bytes;
+        future[0] = 2;
+        assert!(matches!(
+            Signature::from_versioned_bytes(&future),
+            Err(Error::UnsupportedVersion(2))
+        ));
+        assert!(Signature::from_versioned_bytes(&[]).is_err());
+        assert!(Signature::from_versioned_bytes(&[1, 0]).is_err());
+    }
+
+    #[test]
+    fn test_iter_signatures() {
+        let sigs = [
+            Signature([1u8; 65]),
+            Signature([2u8; 65]),
+            Signature([3u8; 65]),
+        ];
+        let bytes: Vec<u8> = sigs.iter().flat_map(|sig| sig.0.to_vec()).collect();
+
+        let decoded: Vec<_> = iter_signatures(&bytes).map(Result::unwrap).collect();
+        assert_eq!(decoded, sigs);
+
+        let decoded: Vec<_> = iter_signatures(&bytes[..bytes.len() - 1]).collect();
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[1].as_ref().unwrap(), &sigs[1]);
+        assert!(decoded[2].is_err());
+        assert_eq!(iter_signatures(&[]).count(), 0);
+    }
+
+    #[test]
+    fn test_recover_compressed() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+
+        let compressed = recover_compressed(&sig, &message).unwrap();
+        assert_eq!(
+            compressed,
+            to_compressed(&sig.recover(&message).unwrap()).unwrap()
+        );
+        assert_eq!(&from_compressed(&compressed).unwrap(), keypair.pubkey());
+    }
+
+    #[test]
+    fn test_sign_verified() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let (sig, address) = sign_verified(keypair.privkey(), &message).unwrap();
+        assert_eq!(address, keypair.address());
+        assert!(sig.verify_address(&address, &message).unwrap());
+        assert!(sign_verified(&PrivKey::zero(), &message).is_err());
+    }
+
+    #[cfg(feature = "secp256k1")]
+    #[test]
+    fn test_recovered_pubkey_layout() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+
+        let pubkey = sig.recover(&message).unwrap();
+        assert_eq!(pubkey.as_bytes().len(), super::PUBKEY_BYTES_LEN);
+        let serialized = to_secp_pubkey(&pubkey).unwrap().serialize_uncompressed();
+        assert_eq!(serialized[0], 0x04);
+        assert_eq!(&serialized[1..], pubkey.as_bytes());
+    }
+
+    #[cfg(feature = "secp256k1")]
+    #[test]
+    fn test_pubkey_canonical_form() {
+        use std::collections::HashSet;
+
+        let keypair = KeyPair::gen_keypair();
+        let public_key = to_secp_pubkey(keypair.pubkey()).unwrap();
+        let from_compressed_bytes = from_compressed(&public_key.serialize()).unwrap();
+        let from_uncompressed_bytes =
+            from_secp_pubkey(&PublicKey::from_slice(&public_key.serialize_uncompressed()).unwrap());
+        assert_eq!(from_compressed_bytes, from_uncompressed_bytes);
+
+        let mut set = HashSet::new();
+        set.insert(from_compressed_bytes);
+        assert!(set.contains(&from_uncompressed_bytes));
+    }
+
+    #[test]
+    fn test_view() {
+        let mut bytes = [0u8; 65];
+        bytes[0..32].copy_from_slice(&[1u8; 32]);
+        bytes[32..64].copy_from_slice(&[2u8; 32]);
+        bytes[64] = 1;
+
+        let (r, s, v) = Signature::view(&bytes).unwrap();
+        assert_eq!(r, &[1u8; 32]);
+        assert_eq!(s, &[2u8; 32]);
+        assert_eq!(v, 1);
+        assert!(std::ptr::eq(r.as_ptr(), bytes.as_ptr()));
+
+        assert!(Signature::view(&bytes[..64]).is_err());
+        assert!(Signature::view(&[0u8; 66]).is_err());
+    }
+
+    #[cfg(feature = "secp256k1")]
+    #[test]
+    fn test_verify_with_secp_pubkey() {
+        let keypair = KeyPair::gen_keypair();
+        let secp_pubkey = to_secp_pubkey(keypair.pubkey()).unwrap();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        let other = Message::hash("other");
+        for message in &[message, other] {
+            assert_eq!(
+                verify_with_secp_pubkey(&secp_pubkey, &sig, message).unwrap(),
+                sig.verify_public(keypair.pubkey(), message).unwrap()
+            );
+        }
+        assert!(verify_with_secp_pubkey(&secp_pubkey, &sig, &message).unwrap());
+    }
+
+    #[test]
+    fn test_verify_public_detailed() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+
+        assert!(matches!(
+            verify_public_detailed(keypair.pubkey(), &sig, &message),
+            VerifyOutcome::Valid
+        ));
+        assert!(matches!(
+            verify_public_detailed(KeyPair::gen_keypair().pubkey(), &sig, &message),
+            VerifyOutcome::WrongSigner
+        ));
+
+        let mut malformed = sig.clone();
+        malformed.0[0..32].copy_from_slice(&[0u8; 32]);
+        assert!(matches!(
+            verify_public_detailed(keypair.pubkey(), &malformed, &message),
+            VerifyOutcome::Malformed(Error::ROutOfRange)
+        ));
+
+        assert!(matches!(
+            verify_public_detailed(&PubKey::zero(), &sig, &message),
+            VerifyOutcome::Error(Error::InvalidPubKey)
+        ));
+    }
+
+    #[test]
+    fn test_v_byte_agnostic_signature() {
+        use std::collections::HashMap;
+
+        let sig = Signature([3u8; 65]);
+        let mut other_v = sig.clone();
+        other_v.0[64] = 4;
+        assert_ne!(sig, other_v);
+
+        let mut map = HashMap::new();
+        map.insert(VByteAgnosticSignature::from(sig), 1);
+        map.insert(VByteAgnosticSignature::from(other_v), 2);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map[&VByteAgnosticSignature(Signature([3u8; 65]))], 2);
+    }
+
+    #[test]
+    fn test_recover_checked() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        assert_eq!(keypair.pubkey(), &recover_checked(&sig, &message).unwrap());
+
+        let mut bad_r = sig.clone();
+        bad_r.0[0..32].copy_from_slice(&[0u8; 32]);
+        assert!(matches!(
+            recover_checked(&bad_r, &message),
+            Err(Error::InvalidSignature)
+        ));
+
+        let mut bad_v = sig.clone();
+        bad_v.0[64] = 4;
+        assert!(matches!(
+            recover_checked(&bad_v, &message),
+            Err(Error::InvalidSignature)
+        ));
+
+        // r + n overflows the field for any realistic r
+        let mut wrong_id = sig;
+        wrong_id.0[64] = 2;
+        assert!(matches!(
+            recover_checked(&wrong_id, &message),
+            Err(Error::Unrecoverable)
+        ));
+    }
+
+    #[test]
+    fn test_normalize_s() {
+        use cita_types::U256;
+
+        fn naive(sig: &Signature) -> Signature {
+            let order = U256::from_big_endian(&super::CURVE_ORDER);
+            let s = U256::from_big_endian(sig.s());
+            let mut out = sig.clone();
+            if s > order / 2 {
+                (order - s).to_big_endian(&mut out.0[32..64]);
+                out.0[64] ^= 1;
+            }
+            out
+        }
+
+        let keypair = KeyPair::gen_keypair();
+        let mut inputs = vec![
+            super::HALF_CURVE_ORDER,
+            U256::from_big_endian(&super::HALF_CURVE_ORDER)
+                .overflowing_add(U256::one())
+                .0
+                .into(),
+            [1u8; 32],
+        ];
+        for i in 0..256u32 {
+            let message = Message::hash(i.to_string());
+            let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+            let (high, _) = super::sub_be(&super::CURVE_ORDER, sig.s().try_into().unwrap());
+            inputs.push(sig.s().try_into().unwrap());
+            inputs.push(high);
+        }
+
+        for s in inputs.iter() {
+            let mut sig = Signature([0u8; 65]);
+            sig.0[32..64].copy_from_slice(s);
+            let expected = naive(&sig);
+            sig.normalize_s();
+            assert_eq!(sig, expected);
+            assert!(sig.is_low_s());
+        }
+    }
+
+    #[test]
+    fn test_boundary_s() {
+        let mut sig = Signature([1u8; 65]);
+        sig.0[32..64].copy_from_slice(&super::HALF_CURVE_ORDER);
+        assert!(sig.is_boundary_s());
+        assert!(sig.is_low_s());
+
+        sig.0[63] += 1;
+        assert!(!sig.is_boundary_s());
+        assert!(!sig.is_low_s());
+
+        sig.0[63] -= 2;
+        assert!(!sig.is_boundary_s());
+        assert!(sig.is_low_s());
+    }
+
+    #[test]
+    fn test_recover_or() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let mut sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        assert_eq!(&sig.recover_or(&message, PubKey::zero()), keypair.pubkey());
+
+        sig.0[64] = 9;
+        assert_eq!(sig.recover_or(&message, PubKey::zero()), PubKey::zero());
+    }
+
+    #[test]
+    fn test_signature_format() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+
+        let full = encode_for(SignatureFormat::Recoverable65, &sig);
+        assert_eq!(full.len(), 65);
+        assert_eq!(
+            decode_for(SignatureFormat::Recoverable65, &full).unwrap(),
+            sig
+        );
+
+        let compact = encode_for(SignatureFormat::Compact64, &sig);
+        assert_eq!(compact.len(), 64);
+        let decoded = decode_for(SignatureFormat::Compact64, &compact).unwrap();
+        assert_eq!(decoded.r(), sig.r());
+        assert_eq!(decoded.s(), sig.s());
+        assert_eq!(decoded.v(), 0);
+        assert!(decoded.verify_public(keypair.pubkey(), &message).unwrap());
+
+        assert!(decode_for(SignatureFormat::Compact64, &full).is_err());
+        assert!(decode_for(SignatureFormat::Recoverable65, &compact).is_err());
+    }
+
+    #[test]
+    fn test_parse_hex_and_base64() {
+        use rustc_serialize::base64::{ToBase64, STANDARD};
+
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+
+        let hex = to_hex(&sig.0);
+        let base64 = sig.0.to_base64(STANDARD);
+        assert_eq!(Signature::from_str(&hex).unwrap(), sig);
+        assert_eq!(Signature::try_from(hex.as_str()).unwrap(), sig);
+        assert_eq!(
+            Signature::try_from(format!("0x{}", hex).as_str()).unwrap(),
+            sig
+        );
+        assert_eq!(Signature::try_from(base64.as_str()).unwrap(), sig);
+
+        assert!(Signature::from_str(&base64).is_err());
+        assert!(Signature::try_from("not a signature").is_err());
+    }
+
+    #[cfg(feature = "secp256k1")]
+    #[test]
+    fn test_sign_with_nonce_vector() {
+        // privkey 1, sha256("Satoshi Nakamoto"), RFC 6979 nonce, from the
+        // widely used secp256k1 deterministic signing fixtures.
+        let privkey = PrivKey::from_low_u64_be(1);
+        let message = Message::from_digest(
+            H256::from_str("a0dc65ffca799873cbea0ac274015b9526505daaaed385155425f7337704883e")
+                .unwrap(),
+        );
+        let k = H256::from_str("8f8a276c19f4149656b280621e358cce24f5f52542772691ee69063b74f15d15")
+            .unwrap();
+        let sig = sign_with_nonce(&privkey, &message, &k.0).unwrap();
+        assert_eq!(
+            to_hex(sig.r()),
+            "934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d8"
+        );
+        assert_eq!(
+            to_hex(sig.s()),
+            "2442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5"
+        );
+        // The C library derives the same nonce, so both must agree.
+        assert_eq!(sig, Signature::sign(&privkey, &message).unwrap());
+        let keypair = KeyPair::from_privkey(privkey).unwrap();
+        assert_eq!(&sig.recover(&message).unwrap(), keypair.pubkey());
+
+        assert!(sign_with_nonce(&privkey, &message, &[0u8; 32]).is_err());
+    }
+
+    #[test]
+    fn test_verify_or_err_short_circuits() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let good = Signature::sign(keypair.privkey(), &message).unwrap();
+        let other = Signature::sign(KeyPair::gen_keypair().privkey(), &message).unwrap();
+        let batch = [good.clone(), other, good];
+
+        let mut checked = 0;
+        let result = batch
+            .iter()
+            .map(|sig| {
+                checked += 1;
+                verify_or_err(keypair.pubkey(), sig, &message)
+            })
+            .collect::<Result<Vec<_>, _>>();
+        assert!(matches!(result, Err(Error::InvalidSignature)));
+        assert_eq!(checked, 2);
+
+        assert!(verify_or_err(keypair.pubkey(), &batch[0], &message).is_ok());
+    }
+
+    #[test]
+    fn test_from_compact() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        let rs: [u8; 64] = sig.0[0..64].try_into().unwrap();
+
+        let rebuilt = Signature::from_compact(&rs, sig.v()).unwrap();
+        assert_eq!(rebuilt, sig);
+        assert_eq!(&rebuilt.recover(&message).unwrap(), keypair.pubkey());
+        assert!(matches!(
+            Signature::from_compact(&rs, 5),
+            Err(Error::VOutOfRange)
+        ));
+    }
+
+    #[test]
+    fn test_sign_privkey_bounds() {
+        let message = Message::hash("");
+        let mut max = super::CURVE_ORDER;
+        max[31] -= 1;
+
+        let privkey = PrivKey::from(max);
+        let sig = Signature::sign(&privkey, &message).unwrap();
+        let keypair = KeyPair::from_privkey(privkey).unwrap();
+        assert!(sig.verify_public(keypair.pubkey(), &message).unwrap());
+
+        assert!(Signature::sign(&PrivKey::from(super::CURVE_ORDER), &message).is_err());
+        assert!(Signature::sign(&PrivKey::zero(), &message).is_err());
+    }
+
+    #[test]
+    fn test_recover_signer_bitmap() {
+        let validators: Vec<KeyPair> = (0..4).map(|_| KeyPair::gen_keypair()).collect();
+        let addresses: Vec<_> = validators.iter().map(|v| v.address()).collect();
+        let message = Message::hash("block");
+
+        let mut sigs: Vec<_> = [3, 1]
+            .iter()
+            .map(|&i| Signature::sign(validators[i].privkey(), &message).unwrap())
+            .collect();
+        sigs.push(Signature::sign(KeyPair::gen_keypair().privkey(), &message).unwrap());
+        sigs.push(Signature([0u8; 65]));
+
+        assert_eq!(
+            recover_signer_bitmap(&sigs, &message, &addresses).unwrap(),
+            vec![false, true, false, true]
+        );
+        assert_eq!(
+            recover_signer_bitmap(&[], &message, &addresses).unwrap(),
+            vec![false; 4]
+        );
+
+        // r = 5 is in range, but no curve point has that x coordinate
+        let mut unrecoverable = Signature([0u8; 65]);
+        unrecoverable.0[31] = 5;
+        unrecoverable.0[63] = 1;
+        assert!(unrecoverable.is_valid());
+        assert!(recover(&unrecoverable, &message).is_err());
+        let sigs = vec![
+            unrecoverable,
+            Signature::sign(validators[2].privkey(), &message).unwrap(),
+        ];
+        assert_eq!(
+            recover_signer_bitmap(&sigs, &message, &addresses).unwrap(),
+            vec![false, false, true, false]
+        );
+    }
+
+    #[test]
+    #[cfg(all(
+        feature = "secp256k1",
+        target_pointer_width = "64",
+        target_endian = "little"
+    ))]
+    fn test_libsecp_recoverable() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+
+        let secret = secp256k1::SecretKey::from_slice(&keypair.privkey().0).unwrap();
+        let native = super::SECP256K1.sign_ecdsa_recoverable(
+            &secp256k1::Message::from_slice(message.as_bytes()).unwrap(),
+            &secret,
+        );
+        let bytes = unsafe { *(native.as_ptr() as *const [u8; 65]) };
+
+        assert_eq!(Signature::from_libsecp_recoverable(&bytes).unwrap(), sig);
+        assert_eq!(sig.to_libsecp_recoverable().unwrap(), bytes);
+        assert_eq!(bytes[64], sig.v());
+        let mut r = sig.r().to_vec();
+        r.reverse();
+        assert_eq!(&bytes[0..32], &r[..]);
+
+        let mut bad_v = bytes;
+        bad_v[64] = 4;
+        assert!(Signature::from_libsecp_recoverable(&bad_v).is_err());
+
+        // s = n, stored little-endian
+        let mut unreduced = bytes;
+        unreduced[32..64].copy_from_slice(&super::CURVE_ORDER);
+        unreduced[32..64].reverse();
+        assert!(Signature::from_libsecp_recoverable(&unreduced).is_err());
+
+        let mut zero_r = bytes;
+        zero_r[0..32].copy_from_slice(&[0u8; 32]);
+        assert!(Signature::from_libsecp_recoverable(&zero_r).is_err());
+    }
+
+    #[test]
+    fn test_rs_bytes() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        assert_eq!(&sig.rs_bytes()[..], &sig.0[0..64]);
+
+        let mut other_v = sig.clone();
+        other_v.0[64] ^= 1;
+        assert_eq!(other_v.rs_bytes(), sig.rs_bytes());
+    }
+
+    #[test]
+    fn test_verify_against_set() {
+        use std::collections::HashSet;
+
+        let allowed: Vec<KeyPair> = (0..3).map(|_| KeyPair::gen_keypair()).collect();
+        let allow: HashSet<_> = allowed.iter().map(|k| k.address()).collect();
+        let message = Message::hash("");
+
+        let sig = Signature::sign(allowed[1].privkey(), &message).unwrap();
+        assert_eq!(
+            verify_against_set(&allow, &sig, &message).unwrap(),
+            Some(allowed[1].address())
+        );
+
+        let outsider = Signature::sign(KeyPair::gen_keypair().privkey(), &message).unwrap();
+        assert_eq!(
+            verify_against_set(&allow, &outsider, &message).unwrap(),
+            None
+        );
+        assert_eq!(
+            verify_against_set(&allow, &Signature::default(), &message).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_eq_slice() {
+        let sig = Signature([7u8; 65]);
+        let fixture: &[u8] = &[7u8; 65];
+        assert_eq!(sig, *fixture);
+        assert_eq!(sig, fixture);
+        assert!(sig != fixture[..64]);
+        assert!(sig != [8u8; 65][..]);
+    }
+
+    #[test]
+    fn test_sign_with_hasher() {
+        use crate::keccak256;
+
+        let keypair = KeyPair::gen_keypair();
+        let data = b"payload";
+        let double_keccak = |d: &[u8]| keccak256(&keccak256(d).0);
+
+        let sig = sign_with_hasher(keypair.privkey(), data, keccak256).unwrap();
+        let other = sign_with_hasher(keypair.privkey(), data, double_keccak).unwrap();
+        assert_ne!(sig, other);
+        assert!(sig
+            .verify_public(keypair.pubkey(), &Message::from_digest(keccak256(data)))
+            .unwrap());
+        assert!(other
+            .verify_public(keypair.pubkey(), &Message::from_digest(double_keccak(data)))
+            .unwrap());
+    }
+
+    #[test]
+    fn test_to_short_string() {
+        let mut sig = Signature([0xab; 65]);
+        sig.0[63] = 0x12;
+        sig.0[64] = 1;
+        let short = sig.to_short_string();
+        assert_eq!(short, "0xababab…abab12 (v=1)");
+        assert_eq!(short.chars().count(), 21);
+        assert!(short.ends_with("(v=1)"));
+    }
+
+    #[test]
+    fn test_verify_public_bytes() {
+        use crate::keccak256;
+
+        let keypair = KeyPair::gen_keypair();
+        let data = b"payload";
+        let sig = sign_with_hasher(keypair.privkey(), data, keccak256).unwrap();
+
+        assert!(verify_public_bytes(keypair.pubkey(), &sig, data, keccak256).unwrap());
+        assert!(
+            !verify_public_bytes(keypair.pubkey(), &sig, data, |d| keccak256(&keccak256(d).0))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_recover_addresses() {
+        let keypairs: Vec<KeyPair> = (0..3).map(|_| KeyPair::gen_keypair()).collect();
+        let mut pairs: Vec<_> = keypairs
+            .iter()
+            .enumerate()
+            .map(|(i, keypair)| {
+                let message = Message::hash(i.to_string());
+                (
+                    Signature::sign(keypair.privkey(), &message).unwrap(),
+                    message,
+                )
+            })
+            .collect();
+        let expected: Vec<_> = keypairs.iter().map(|k| k.address()).collect();
+        assert_eq!(recover_addresses(&pairs).unwrap(), expected);
+
+        pairs[1].0 .0[64] = 9;
+        assert!(recover_addresses(&pairs).is_err());
+    }
+
+    #[test]
+    fn test_verify_with_commitment() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+
+        let commitment = H256::from(message);
+        assert!(verify_with_commitment(keypair.pubkey(), &sig, &message, &commitment).unwrap());
+        let other = H256::from(Message::hash("other"));
+        assert!(!verify_with_commitment(keypair.pubkey(), &sig, &message, &other).unwrap());
+        // no crypto error even for a key that can not be parsed
+        assert!(!verify_with_commitment(&PubKey::zero(), &sig, &message, &other).unwrap());
+    }
+
+    #[test]
+    fn test_recover_h256() {
+        let keypair = KeyPair::gen_keypair();
+        let digest = H256::from_low_u64_be(42);
+        let sig = Signature::sign(keypair.privkey(), &Message::from_digest(digest)).unwrap();
+        assert_eq!(
+            recover_h256(&sig, &digest).unwrap(),
+            sig.recover(&Message::from_digest(digest)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_has_canonical_recovery() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        assert!(sig.has_canonical_recovery(&message).unwrap());
+
+        let mut inflated = sig.clone();
+        inflated.0[64] += 27;
+        assert!(!inflated.has_canonical_recovery(&message).unwrap());
+
+        let mut unrecoverable = sig;
+        unrecoverable.0[64] = 9;
+        assert!(unrecoverable.has_canonical_recovery(&message).is_err());
+    }
+
+    #[test]
+    fn test_try_from_compact_array() {
+        let mut rs = [0u8; 64];
+        rs[0..32].copy_from_slice(&[1u8; 32]);
+        rs[32..64].copy_from_slice(&[2u8; 32]);
+        let sig = Signature::try_from(&rs).unwrap();
+        assert_eq!(sig.r(), &[1u8; 32]);
+        assert_eq!(sig.s(), &[2u8; 32]);
+        assert_eq!(sig.v(), 0);
+    }
+
+    #[test]
+    fn test_sign_hedged() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let first = sign_hedged(keypair.privkey(), &message, &[1u8; 32]).unwrap();
+        let second = sign_hedged(keypair.privkey(), &message, &[2u8; 32]).unwrap();
+        assert_ne!(first, second);
+        assert_ne!(first, Signature::sign(keypair.privkey(), &message).unwrap());
+        for sig in &[first, second] {
+            assert!(sig.is_low_s());
+            assert_eq!(sig.recover(&message).unwrap(), *keypair.pubkey());
+        }
+    }
+
+    #[test]
+    fn test_eip2098() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        for data in &["a", "b", "c", "d"] {
+            let message = Message::hash(data);
+            let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+            let compact = sig.to_eip2098();
+            assert_eq!(compact[32] >> 7, sig.v());
+            let back = Signature::from_eip2098(&compact).unwrap();
+            assert_eq!(back, sig);
+            assert_eq!(back.recover(&message).unwrap(), *keypair.pubkey());
+        }
+
+        // s above half the order is not a valid EIP-2098 encoding
+        let mut compact = Signature::sign(keypair.privkey(), &message)
+            .unwrap()
+            .to_eip2098();
+        compact[32..64].copy_from_slice(&[0xff; 32]);
+        compact[32] = 0x7f;
+        assert!(Signature::from_eip2098(&compact).is_err());
+    }
+
+    #[test]
+    fn test_rs_arrays() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        assert_eq!(&sig.r_array()[..], sig.r());
+        assert_eq!(&sig.s_array()[..], sig.s());
+    }
+
+    #[test]
+    fn test_into_iter() {
+        let keypair = KeyPair::gen_keypair();
+        let sig = Signature::sign(keypair.privkey(), &Message::hash("")).unwrap();
+        let mut bytes = Vec::new();
+        for b in &sig {
+            bytes.push(*b);
+        }
+        assert_eq!(bytes.len(), 65);
+        assert_eq!(bytes[..], sig.0[..]);
+    }
+
+    #[test]
+    fn test_verify_address_strict() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        assert!(verify_address_strict(&keypair.address(), &sig, &message).unwrap());
+        assert!(!verify_address_strict(&Address::zero(), &sig, &message).unwrap());
+
+        // no known signature recovers to zero, so check the final comparison
+        match match_nonzero_signer(&Address::zero(), &Address::zero()) {
+            Err(Error::InvalidAddress) => {}
+            other => panic!("zero signer accepted: {:?}", other),
+        }
+        assert!(match_nonzero_signer(&keypair.address(), &keypair.address()).unwrap());
+    }
+
+    #[test]
+    fn test_upper_hex() {
+        let keypair = KeyPair::gen_keypair();
+        let sig = Signature::sign(keypair.privkey(), &Message::hash("")).unwrap();
+        let upper = format!("{:X}", sig);
+        assert_eq!(upper.len(), 130);
+        assert_eq!(upper, format!("{:x}", sig).to_uppercase());
+        assert!(!upper.chars().any(|c| c.is_ascii_lowercase()));
+    }
+
+    #[test]
+    fn test_recover_nonzero() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        assert_eq!(recover_nonzero(&sig, &message).unwrap(), *keypair.pubkey());
+
+        let zero = Message::default();
+        let sig = Signature::sign(keypair.privkey(), &zero).unwrap();
+        assert_eq!(sig.recover(&zero).unwrap(), *keypair.pubkey());
+        match recover_nonzero(&sig, &zero) {
+            Err(Error::InvalidMessage) => {}
+            other => panic!("zero message accepted: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_to_from_bytes() {
+        let keypair = KeyPair::gen_keypair();
+        let sig = Signature::sign(keypair.privkey(), &Message::hash("")).unwrap();
+        let bytes = sig.to_bytes();
+        assert_eq!(bytes, sig.0);
+        assert_eq!(Signature::from_bytes(bytes), sig);
+    }
+
+    #[test]
+    fn test_to_cita_tx_signature() {
+        // A CITA v1 `Transaction` protobuf: nonce "fixture-nonce-1", quota
+        // 10000000, valid_until_block 88, data a9059cbb, zero value, version 2,
+        // to_v1 ffff..020004 and chain_id_v1 1. Hashed with keccak256 and
+        // signed (RFC 6979, low s) by an independent Python implementation,
+        // cross-checked against the cryptography package.
+        let tx = from_hex(
+            "120f666978747572652d6e6f6e63652d311880ade20420582a04a9059cbb3220\
+             0000000000000000000000000000000000000000000000000000000000000000\
+             40024a14ffffffffffffffffffffffffffffffffff0200045220000000000000\
+             0000000000000000000000000000000000000000000000000001",
+        )
+        .unwrap();
+        let privkey =
+            H256::from_str("24787df1fa5fe112d022710f25c328e677fc15c57dba617c1ac70c642f1841ca")
+                .unwrap();
+        let tx_hash =
+            H256::from_str("31406e1b56ba3b42162f89f645ee77370ac136007f988692fc5fad332417aabd")
+                .unwrap();
+        let expected = "c930b9054ac14afb483642c84b3edf51a83c3d764b2b74127af427ec9e664972\
+                        23feb4b02b9d1ebf765a3bf7ea01fa7b9a43bbcd932a903fe0c0efc232aa0614\
+                        01";
+        let sender = "4cedcf9d8d5c9e5b88ad3a988d21f10f0485bf7a";
+        assert_eq!(keccak256(&tx), tx_hash);
+
+        let message = Message::from_digest(tx_hash);
+        let sig = Signature::sign(&privkey, &message).unwrap();
+        assert_eq!(to_hex(&sig.to_cita_tx_signature()), expected);
+
+        let decoded = Signature::from(&from_hex(expected).unwrap()[..]);
+        let pubkey = decoded.recover(&message).unwrap();
+        assert_eq!(to_hex(&keccak256(pubkey.as_bytes())[12..]), sender);
+    }
+
+    #[test]
+    fn test_from_bitcoin_message_sig() {
+        // from Bitcoin Core's rpc_signmessage.py: key
+        // cUeKHd5orzT3mz8P9pxyREHfsWtVfgsfDjiZZBcjUBAaGk1BTj7N signing
+        // "This is just a test message", digest precomputed
+        let privkey = PrivKey::from(
+            H256::from_str("d2b8a0116d641fe7d3036f8464628fb595b480414c13a301b3d4038c811c28b0")
+                .unwrap(),
+        );
+        let digest = Message::from_digest(
+            H256::from_str("0fcc896d51b842ea407bb1ec0eeb6d0ba46d1109037821a8f6c2737767ab1a1c")
+                .unwrap(),
+        );
+        let encoded = "INbVnW4e6PeRmsv2Qgu8NuopvrVjkcxob+sX8OcZG0SALhWybUjzMLPdAsXI46YZGb0KQTRii+wWIQzRpG/U+S0=";
+        let bytes: [u8; 65] = from_base64(encoded).unwrap()[..].try_into().unwrap();
+
+        let (sig, compressed) = Signature::from_bitcoin_message_sig(&bytes).unwrap();
+        assert!(compressed);
+        assert_eq!(sig.v(), 1);
+        let keypair = KeyPair::from_privkey(privkey).unwrap();
+        assert_eq!(sig.recover(&digest).unwrap(), *keypair.pubkey());
+
+        let mut uncompressed = bytes;
+        uncompressed[0] -= 4;
+        let (other, compressed) = Signature::from_bitcoin_message_sig(&uncompressed).unwrap();
+        assert!(!compressed);
+        assert_eq!(other, sig);
+
+        for header in &[26, 35] {
+            let mut bad = bytes;
+            bad[0] = *header;
+            assert!(Signature::from_bitcoin_message_sig(&bad).is_err());
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        assert!(sig.validate().is_ok());
+
+        let mut bad_r = sig.clone();
+        bad_r.0[0..32].copy_from_slice(&[0u8; 32]);
+        assert!(matches!(bad_r.validate(), Err(Error::ROutOfRange)));
+
+        let mut bad_s = sig.clone();
+        bad_s.0[32..64].copy_from_slice(&[0xffu8; 32]);
+        assert!(matches!(bad_s.validate(), Err(Error::SOutOfRange)));
+
+        let mut bad_v = sig;
+        bad_v.0[64] = 2;
+        assert!(matches!(bad_v.validate(), Err(Error::VOutOfRange)));
+        assert!(!bad_v.is_valid());
+    }
+
+    #[cfg(feature = "k256-interop")]
+    #[test]
+    fn test_k256_roundtrip() {
+        use k256::elliptic_curve::sec1::ToEncodedPoint;
+
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("k256");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+
+        let k256_sig = sig.to_k256().unwrap();
+        assert_eq!(Signature::from_k256(&k256_sig), sig);
+
+        let verifying_key = k256_sig
+            .recover_verifying_key_from_digest_bytes(&(*message.as_fixed_bytes()).into())
+            .unwrap();
+        let encoded = verifying_key.to_encoded_point(false);
+        assert_eq!(
+            &encoded.as_bytes()[1..65],
+            sig.recover(&message).unwrap().as_bytes()
+        );
+    }
 }
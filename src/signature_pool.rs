@@ -0,0 +1,84 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::Signature;
+
+/// Pool of `Signature`s for decoders that reuse them.
+///
+/// A `Signature` is a plain 65-byte array, so acquiring one never allocates
+/// either way; the pool only helps code that keeps signatures boxed or in
+/// buffers it wants to recycle. At most `capacity` released signatures are
+/// kept, the rest are dropped.
+#[derive(Debug, Clone)]
+pub struct SignaturePool {
+    free: Vec<Signature>,
+    capacity: usize,
+}
+
+impl SignaturePool {
+    pub fn new(capacity: usize) -> SignaturePool {
+        SignaturePool {
+            free: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Take a zeroed signature, reusing a released one if there is any.
+    pub fn acquire(&mut self) -> Signature {
+        match self.free.pop() {
+            Some(mut sig) => {
+                sig.0 = [0u8; 65];
+                sig
+            }
+            None => Signature::default(),
+        }
+    }
+
+    /// Hand a signature back, dropping it if the pool is full.
+    pub fn release(&mut self, sig: Signature) {
+        if self.free.len() < self.capacity {
+            self.free.push(sig);
+        }
+    }
+
+    /// Number of signatures waiting to be reused.
+    pub fn len(&self) -> usize {
+        self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.free.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Signature, SignaturePool};
+
+    #[test]
+    fn test_acquire_release() {
+        let mut pool = SignaturePool::new(1);
+        assert!(pool.is_empty());
+
+        let mut sig = pool.acquire();
+        assert_eq!(sig, Signature::default());
+        sig.0[0] = 1;
+        pool.release(sig);
+        pool.release(Signature([2u8; 65]));
+        assert_eq!(pool.len(), 1);
+
+        assert_eq!(pool.acquire(), Signature::default());
+        assert!(pool.is_empty());
+    }
+}
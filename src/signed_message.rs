@@ -0,0 +1,86 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{sign, verify_public, Error, KeyPair, Message, PrivKey, PubKey, Signature};
+use cita_crypto_trait::CreateKey;
+
+/// A message together with its signature and the signer's public key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedMessage {
+    signature: Signature,
+    pubkey: PubKey,
+    message: Message,
+}
+
+impl SignedMessage {
+    /// Sign `message` and record the public key of `privkey` with it.
+    pub fn new(privkey: &PrivKey, message: Message) -> Result<SignedMessage, Error> {
+        let keypair = KeyPair::from_privkey(*privkey)?;
+        Ok(SignedMessage {
+            signature: sign(privkey, &message)?,
+            pubkey: *keypair.pubkey(),
+            message,
+        })
+    }
+
+    /// Bundle parts received from elsewhere, `verify` before trusting them.
+    pub fn from_parts(signature: Signature, pubkey: PubKey, message: Message) -> SignedMessage {
+        SignedMessage {
+            signature,
+            pubkey,
+            message,
+        }
+    }
+
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    pub fn pubkey(&self) -> &PubKey {
+        &self.pubkey
+    }
+
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    /// Check the signature over the message against the recorded public key.
+    pub fn verify(&self) -> Result<bool, Error> {
+        verify_public(&self.pubkey, &self.signature, &self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{KeyPair, Message, PrivKey};
+    use super::SignedMessage;
+    use cita_crypto_trait::CreateKey;
+
+    #[test]
+    fn test_signed_message() {
+        let keypair = KeyPair::gen_keypair();
+        let signed = SignedMessage::new(keypair.privkey(), Message::hash("")).unwrap();
+        assert_eq!(signed.pubkey(), keypair.pubkey());
+        assert!(signed.verify().unwrap());
+
+        let tampered = SignedMessage::from_parts(
+            signed.signature().clone(),
+            *signed.pubkey(),
+            Message::hash("other"),
+        );
+        assert!(!tampered.verify().unwrap());
+
+        assert!(SignedMessage::new(&PrivKey::zero(), Message::hash("")).is_err());
+    }
+}
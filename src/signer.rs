@@ -15,12 +15,26 @@
 use super::{Address, KeyPair, PrivKey};
 use cita_crypto_trait::CreateKey;
 
-#[derive(Default)]
+/// Signing identity of an account.
+///
+/// Signers hold no secp256k1 context of their own, every signer goes through
+/// the default backend's global context, so its precomputed tables are shared.
 pub struct Signer {
     pub keypair: KeyPair,
     pub address: Address,
 }
 
+/// Signer of a freshly generated key pair.
+impl Default for Signer {
+    fn default() -> Self {
+        let keypair = KeyPair::default();
+        Signer {
+            address: keypair.address(),
+            keypair,
+        }
+    }
+}
+
 impl From<PrivKey> for Signer {
     fn from(k: PrivKey) -> Self {
         let keypair = KeyPair::from_privkey(k).unwrap();
@@ -30,3 +44,23 @@ impl From<PrivKey> for Signer {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Signer;
+    use crate::{sign, verify_address, Message};
+    use cita_crypto_trait::CreateKey;
+
+    #[test]
+    fn test_signers_share_context() {
+        let signers: Vec<Signer> = (0..2)
+            .map(|_| Signer::from(*crate::KeyPair::gen_keypair().privkey()))
+            .collect();
+        let message = Message::hash("");
+        for signer in &signers {
+            let sig = sign(signer.keypair.privkey(), &message).unwrap();
+            assert!(verify_address(&signer.address, &sig, &message).unwrap());
+        }
+        assert_ne!(signers[0].address, signers[1].address);
+    }
+}
@@ -0,0 +1,102 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{pubkey_to_address, recover, Address, Error, Message, Signature};
+use std::collections::{HashMap, HashSet};
+
+/// Signers recovered while processing a block.
+///
+/// The same signature over the same message, e.g. a vote relayed by several
+/// peers, is only recovered once: later `add`s of the pair are answered from
+/// the cache. A signer's signature over a different message still has to be
+/// recovered, there is no cheaper way to learn who made it.
+#[derive(Debug, Default, Clone)]
+pub struct SignerSet {
+    recovered: HashMap<(Signature, Message), Address>,
+    signers: HashSet<Address>,
+}
+
+impl SignerSet {
+    pub fn new() -> SignerSet {
+        SignerSet::default()
+    }
+
+    /// Recover the signer of `sig` over `msg` and record it.
+    pub fn add(&mut self, sig: &Signature, msg: &Message) -> Result<Address, Error> {
+        if let Some(address) = self.recovered.get(&(sig.clone(), *msg)) {
+            return Ok(*address);
+        }
+        let address = pubkey_to_address(&recover(sig, msg)?);
+        self.recovered.insert((sig.clone(), *msg), address);
+        self.signers.insert(address);
+        Ok(address)
+    }
+
+    pub fn contains(&self, address: &Address) -> bool {
+        self.signers.contains(address)
+    }
+
+    /// Number of distinct signers.
+    pub fn len(&self) -> usize {
+        self.signers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signers.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{KeyPair, Message, Signature};
+    use super::SignerSet;
+    use cita_crypto_trait::{CreateKey, Sign};
+
+    #[test]
+    fn test_add() {
+        let keypairs = [KeyPair::gen_keypair(), KeyPair::gen_keypair()];
+        let mut signers = SignerSet::new();
+        assert!(signers.is_empty());
+
+        for (i, data) in ["a", "b", "c"].iter().enumerate() {
+            let keypair = &keypairs[i % 2];
+            let message = Message::hash(data);
+            let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+            assert_eq!(signers.add(&sig, &message).unwrap(), keypair.address());
+        }
+        assert_eq!(signers.len(), 2);
+        assert!(signers.contains(&keypairs[1].address()));
+
+        let message = Message::hash("d");
+        let mut sig = Signature::sign(keypairs[0].privkey(), &message).unwrap();
+        sig.0[64] = 9;
+        assert!(signers.add(&sig, &message).is_err());
+        assert_eq!(signers.len(), 2);
+    }
+
+    #[test]
+    fn test_add_cached() {
+        let keypair = KeyPair::gen_keypair();
+        let message = Message::hash("vote");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+        let mut signers = SignerSet::new();
+        assert_eq!(signers.add(&sig, &message).unwrap(), keypair.address());
+
+        // a cached pair is answered without recovery, so a poisoned entry shows
+        let other = KeyPair::gen_keypair().address();
+        signers.recovered.insert((sig.clone(), message), other);
+        assert_eq!(signers.add(&sig, &message).unwrap(), other);
+        assert_eq!(signers.len(), 1);
+    }
+}
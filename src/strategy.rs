@@ -0,0 +1,56 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! `proptest` strategies for property tests.
+//!
+//! `KeyPair` is not `Debug`, which proptest requires of generated values,
+//! so key pairs are generated as their private key.
+
+use super::{privkey_from_be_bytes, Address, PrivKey, Signature};
+use proptest::prelude::*;
+
+/// Any 65 bytes, including out of range and unrecoverable signatures.
+pub fn any_signature() -> impl Strategy<Value = Signature> {
+    any::<[u8; 65]>().prop_map(Signature)
+}
+
+/// A valid private key, pass it to `KeyPair::from_privkey` for the pair.
+pub fn any_privkey() -> impl Strategy<Value = PrivKey> {
+    any::<[u8; 32]>().prop_filter_map("not a valid scalar", |bytes| {
+        privkey_from_be_bytes(&bytes).ok()
+    })
+}
+
+pub fn any_address() -> impl Strategy<Value = Address> {
+    any::<[u8; 20]>().prop_map(Address::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::any_privkey;
+    use crate::{pubkey_to_address, recover, sign, KeyPair, Message};
+    use cita_crypto_trait::CreateKey;
+    use proptest::prelude::*;
+
+    proptest! {
+        #[test]
+        fn test_sign_recover_address(privkey in any_privkey(), digest in any::<[u8; 32]>()) {
+            let keypair = KeyPair::from_privkey(privkey).unwrap();
+            let message = Message::from_digest(digest.into());
+            let sig = sign(&privkey, &message).unwrap();
+            let pubkey = recover(&sig, &message).unwrap();
+            prop_assert_eq!(pubkey_to_address(&pubkey), keypair.address());
+        }
+    }
+}
@@ -0,0 +1,70 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::{Backend, DefaultBackend, Error, Message, PreparedMessage, PubKey, Signature};
+
+/// Verifying identity of a known signer.
+///
+/// The public key is parsed once at construction, instead of on every
+/// `verify_public` call.
+pub struct Verifier {
+    pubkey: PubKey,
+    parsed: <DefaultBackend as Backend>::PublicKey,
+}
+
+impl Verifier {
+    pub fn new(pubkey: PubKey) -> Result<Verifier, Error> {
+        Ok(Verifier {
+            parsed: DefaultBackend::parse_pubkey(&pubkey)?,
+            pubkey,
+        })
+    }
+
+    pub fn pubkey(&self) -> &PubKey {
+        &self.pubkey
+    }
+
+    pub fn verify(&self, sig: &Signature, msg: &Message) -> Result<bool, Error> {
+        DefaultBackend::verify_parsed(&self.parsed, sig, msg)
+    }
+
+    /// Verify over a message parsed once, with neither the key nor the
+    /// message parsed again.
+    pub fn verify_prepared(
+        &self,
+        sig: &Signature,
+        prepared: &PreparedMessage,
+    ) -> Result<bool, Error> {
+        DefaultBackend::verify_prepared(&self.parsed, sig, prepared.parsed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{KeyPair, Message, PubKey, Signature};
+    use super::Verifier;
+    use cita_crypto_trait::{CreateKey, Sign};
+
+    #[test]
+    fn test_verify() {
+        let keypair = KeyPair::gen_keypair();
+        let verifier = Verifier::new(*keypair.pubkey()).unwrap();
+        let message = Message::hash("");
+        let sig = Signature::sign(keypair.privkey(), &message).unwrap();
+
+        assert!(verifier.verify(&sig, &message).unwrap());
+        assert!(!verifier.verify(&sig, &Message::hash("other")).unwrap());
+        assert!(Verifier::new(PubKey::zero()).is_err());
+    }
+}
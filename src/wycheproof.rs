@@ -0,0 +1,71 @@
+// Copyright Rivtower Technologies LLC.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conformance tests against Google's Wycheproof ECDSA vectors.
+//!
+//! `testdata/wycheproof/ecdsa_secp256k1_sha256_bitcoin_test.json` is a subset
+//! of `testvectors/ecdsa_secp256k1_sha256_bitcoin_test.json` from
+//! https://github.com/google/wycheproof (Apache-2.0, generator 0.9rc5): the
+//! first test group with all of its DER, range and arithmetic edge cases, and
+//! the last nine groups with edge case public keys. PEM and DER encodings of
+//! the keys were dropped. The bitcoin variant is used because it expects
+//! high s to be rejected, which is what `verify_public` does.
+
+use super::encoding::from_hex;
+use super::{verify_public, Message, PubKey, Signature};
+use cita_types::H256;
+use sha2::{Digest, Sha256};
+
+const VECTORS: &str =
+    include_str!("../testdata/wycheproof/ecdsa_secp256k1_sha256_bitcoin_test.json");
+
+fn hex(value: &serde_json::Value) -> Vec<u8> {
+    from_hex(value.as_str().unwrap()).unwrap()
+}
+
+/// A signature that does not parse as DER counts as rejected.
+fn accepts(pubkey: &PubKey, msg: &[u8], der: &[u8]) -> bool {
+    let message = Message::from_digest(H256::from_slice(&Sha256::digest(msg)));
+    match Signature::from_der(der) {
+        Ok(sig) => verify_public(pubkey, &sig, &message).unwrap_or(false),
+        Err(_) => false,
+    }
+}
+
+#[test]
+fn test_wycheproof_ecdsa_bitcoin() {
+    let vectors: serde_json::Value = serde_json::from_str(VECTORS).unwrap();
+    let mut count = 0;
+    for group in vectors["testGroups"].as_array().unwrap() {
+        let key = hex(&group["publicKey"]["uncompressed"]);
+        assert_eq!(key[0], 4);
+        let pubkey = PubKey::from_slice(&key[1..]);
+        for test in group["tests"].as_array().unwrap() {
+            let expected = match test["result"].as_str().unwrap() {
+                "valid" => true,
+                "invalid" => false,
+                other => panic!("unexpected result {}", other),
+            };
+            assert_eq!(
+                accepts(&pubkey, &hex(&test["msg"]), &hex(&test["sig"])),
+                expected,
+                "tcId {} ({})",
+                test["tcId"],
+                test["comment"]
+            );
+            count += 1;
+        }
+    }
+    assert_eq!(count, vectors["numberOfTests"].as_u64().unwrap());
+}